@@ -1,72 +1,347 @@
-use std::{error, fmt, str::FromStr};
+use std::{error, fmt, path::PathBuf, str::FromStr};
 
-use chrono::{Date, TimeZone, Utc};
-use clap::Parser;
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand};
+
+use crate::{filter::Rule, output::Format, profile::Profile, recording::Recording, size::Size};
+
+#[derive(Clone, Debug, Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Args,
+}
+
+impl Cli {
+    pub fn parse() -> Self {
+        Parser::parse()
+    }
+
+    /// Running the feeds is what happens when no command is given.
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Run(Box::new(self.run)))
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// check feeds for new uploads (the default)
+    Run(Box<Args>),
+
+    /// keep running, polling each feed on its own interval until interrupted
+    Watch(Box<WatchArgs>),
+
+    /// inspect or edit history
+    History(HistoryArgs),
+}
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub run: Args,
+
+    /// minutes between polls of each feed
+    ///
+    /// Overrides the interval set in the config, for every feed.
+    #[arg(long)]
+    pub interval: Option<u64>,
+}
+
+// Where config and history live
+#[derive(Clone, Debug, clap::Args)]
+pub struct Store {
+    /// profile name
+    ///
+    /// Each profile keeps its own config and history under the user data directory.
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// history path
+    ///
+    /// Overrides the profile's history database.
+    #[arg(long)]
+    pub history: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub command: HistoryCommand,
+
+    #[command(flatten)]
+    pub store: Store,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// list magnets from past listings, newest first
+    List {
+        /// only magnets from this feed
+        #[arg(long)]
+        feed: Option<String>,
+
+        /// only magnets first seen on or after YYYY-MM-DD
+        #[arg(long)]
+        since: Option<ArgDate>,
+
+        /// only magnets first seen on or before YYYY-MM-DD
+        #[arg(long)]
+        until: Option<ArgDate>,
+
+        /// include magnets that were seen but filtered out
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// search titles from past listings
+    Search {
+        /// text to look for, ignoring case
+        text: String,
+
+        /// include magnets that were seen but filtered out
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// forget a magnet so it will be listed again
+    Forget {
+        /// infohash of the magnet
+        infohash: String,
+    },
+
+    /// remove magnets uploaded before a date
+    Prune {
+        /// YYYY-MM-DD, beginning at midnight in the config's timezone
+        #[arg(long)]
+        before: ArgDate,
+
+        /// config to read the timezone from; defaults to the profile's config.toml
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+
+    /// show how much each feed has produced
+    Stats,
+}
 
 #[derive(Clone, Debug, Parser)]
-#[command(version)]
 pub struct Args {
     /// config path
     ///
-    /// Should be a file containing URLs for all pages to watch.
-    pub path: String,
+    /// Should be a TOML subscription file (*.toml) or a file containing URLs for all pages to
+    /// watch, one per line. Defaults to the profile's config.toml.
+    path: Option<String>,
+
+    #[command(flatten)]
+    pub store: Store,
 
     /// all after date
     ///
-    /// Filters recent uploads by YYYY-MM-DD.
+    /// Filters recent uploads by YYYY-MM-DD, or a relative date like 7d or yesterday. Overrides
+    /// any per-feed date in the config, and the default of picking up from each feed's last
+    /// successful run.
     #[arg(short = 'd', long = "date")]
-    after: Option<ArgDate>,
+    pub after: Option<ArgDate>,
 
     /// output path
     #[arg(short, long)]
     output: Option<String>,
 
+    /// output format
+    #[arg(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// magnet directory
+    ///
+    /// Also write one <infohash>.magnet file per new item into this directory, for clients which
+    /// watch a folder. Overrides any magnet directory in the config.
+    #[arg(long)]
+    pub magnet_dir: Option<PathBuf>,
+
     /// page limit
     ///
-    /// Limits the number of pages the program will review for a given subscription.
+    /// Limits the number of pages the program will review for a given subscription. Paging stops
+    /// sooner once a page holds nothing new. Overrides any page limit in the config.
+    #[arg(short, long)]
+    pub pages: Option<usize>,
+
+    /// minimum size
+    ///
+    /// e.g. 500MiB or 1.5GiB. Overrides any minimum size in the config.
+    #[arg(long)]
+    pub min_size: Option<Size>,
+
+    /// maximum size
+    ///
+    /// e.g. 500MiB or 1.5GiB. Overrides any maximum size in the config.
+    #[arg(long)]
+    pub max_size: Option<Size>,
+
+    /// minimum seeders
+    ///
+    /// Overrides any minimum seeder count in the config.
+    #[arg(long)]
+    pub min_seeders: Option<u32>,
+
+    /// trusted uploader
+    ///
+    /// Keep only uploads by these users. May be given more than once; replaces any uploader
+    /// list in the config.
+    #[arg(short, long = "uploader")]
+    pub uploaders: Vec<String>,
+
+    /// include rule
+    ///
+    /// Keep only titles matching at least one include rule. A rule is a case-insensitive
+    /// keyword, or a regex between slashes (/720p|1080p/, or /720p|1080p/i to ignore case). May
+    /// be given more than once; replaces any global include rules in the config.
     #[arg(short, long)]
-    pages: Option<usize>,
+    pub include: Vec<Rule>,
+
+    /// exclude rule
+    ///
+    /// Drop titles matching any exclude rule. Same syntax as --include; replaces any global
+    /// exclude rules in the config.
+    #[arg(short = 'x', long)]
+    pub exclude: Vec<Rule>,
+
+    /// drop near-duplicates
+    ///
+    /// Groups titles which differ only by resolution, codec, container or punctuation, and
+    /// keeps one magnet from each group.
+    #[arg(long)]
+    pub dedup: bool,
+
+    /// quality preference
+    ///
+    /// Picks among near-duplicates: the first rule to match wins. Same syntax as --include. May
+    /// be given more than once; replaces any quality order in the config.
+    #[arg(short, long)]
+    pub quality: Vec<Rule>,
+
+    /// explain filtering
+    ///
+    /// Print the reason each dropped item was dropped.
+    #[arg(long)]
+    pub explain: bool,
 
     /// wait time between requests
+    ///
+    /// Applies to each host separately.
     #[arg(short, long)]
     pub wait: Option<u64>,
+
+    /// number of feeds to fetch at once
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// mirror base url
+    ///
+    /// May be given more than once. Mirrors are tried in order, moving on to the next whenever a
+    /// request fails to connect or the server returns a 5xx. Only feeds on one of the mirrors
+    /// fail over; feeds on any other host are fetched from that host alone.
+    #[arg(short, long = "mirror")]
+    pub mirrors: Vec<String>,
+
+    /// save fetched pages
+    ///
+    /// Writes every page fetched into this directory, one file per url, for replaying later or
+    /// attaching to a bug report.
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// serve pages from a recording
+    ///
+    /// Reads pages saved by --record instead of going to the network.
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
 }
 
 impl Args {
-    pub fn parse() -> Self {
-        Parser::parse()
+    /// The cutoff given by `--date`, taken as midnight in `tz`, or else three days ago.
+    pub fn take_after(&self, tz: Tz) -> DateTime<Utc> {
+        self.after
+            .map(|after| start_of_day(after.into_inner(), tz))
+            .unwrap_or_else(|| Utc::now() - chrono::Duration::days(3))
     }
 
-    pub fn take_after(&self) -> Date<Utc> {
-        self.after
-            .map(|date| date.0)
-            .unwrap_or_else(|| (Utc::now() + chrono::Duration::days(-3)).date())
+    pub fn config_path(&self, profile: &Profile) -> PathBuf {
+        self.path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| profile.config_path())
     }
 
     pub fn output(&self) -> Option<&str> {
         self.output.as_deref()
     }
 
-    pub fn page_limit(&self) -> usize {
-        self.pages.unwrap_or(3)
+    pub fn recording(&self) -> Recording {
+        match (&self.record, &self.replay) {
+            (_, Some(dir)) => Recording::Replay(dir.clone()),
+            (Some(dir), None) => Recording::Record(dir.clone()),
+            (None, None) => Recording::Off,
+        }
     }
 }
 
+/// A date from the command line or config
+///
+/// Either YYYY-MM-DD, or relative to today (in UTC): `today`, `yesterday`, or a number of days
+/// ago like `7d`.
 #[derive(Copy, Clone, Debug)]
-struct ArgDate(Date<Utc>);
+pub struct ArgDate(NaiveDate);
 
-impl FromStr for ArgDate {
-    type Err = ParseArgDateErr;
+impl ArgDate {
+    pub fn into_inner(self) -> NaiveDate {
+        self.0
+    }
+
+    fn parse_relative_to(s: &str, today: NaiveDate) -> Result<Self, ParseArgDateErr> {
+        let days_ago = match s {
+            "today" => Some(0),
+            "yesterday" => Some(1),
+            _ => s.strip_suffix('d').and_then(|days| days.parse().ok()),
+        };
+        if let Some(days_ago) = days_ago {
+            return today
+                .checked_sub_days(Days::new(days_ago))
+                .map(ArgDate)
+                .ok_or(ParseArgDateErr::NoSuchDate);
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut s = s.split('-');
         let year = parse_with_error(s.next(), ParseArgDateErr::Year)?;
         let month = parse_with_error(s.next(), ParseArgDateErr::Month)?;
         let day = parse_with_error(s.next(), ParseArgDateErr::Day)?;
-        Ok(ArgDate(Utc.ymd(year, month, day)))
+        NaiveDate::from_ymd_opt(year, month, day)
+            .map(ArgDate)
+            .ok_or(ParseArgDateErr::NoSuchDate)
     }
 }
 
+impl FromStr for ArgDate {
+    type Err = ParseArgDateErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_relative_to(s, Utc::now().date_naive())
+    }
+}
+
+/// The instant a day begins in a timezone. Should the clocks skip midnight, the day is taken
+/// to begin at midnight UTC instead.
+pub fn start_of_day(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
 fn parse_with_error<T: FromStr>(
     s: Option<&str>,
     error: ParseArgDateErr,
@@ -74,11 +349,13 @@ fn parse_with_error<T: FromStr>(
     s.ok_or(error)?.parse().map_err(|_| error)
 }
 
-#[derive(Clone, Copy, Debug)]
-enum ParseArgDateErr {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseArgDateErr {
     Year,
     Month,
     Day,
+    /// Well-formed, but not on the calendar (e.g. 2023-02-30).
+    NoSuchDate,
 }
 
 impl fmt::Display for ParseArgDateErr {
@@ -87,8 +364,100 @@ impl fmt::Display for ParseArgDateErr {
             ParseArgDateErr::Year => f.write_str("bad year"),
             ParseArgDateErr::Month => f.write_str("bad month"),
             ParseArgDateErr::Day => f.write_str("bad day"),
+            ParseArgDateErr::NoSuchDate => f.write_str("no such date"),
         }
     }
 }
 
 impl error::Error for ParseArgDateErr {}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use chrono::NaiveDate;
+
+    use super::{ArgDate, Cli, Command, HistoryCommand, ParseArgDateErr};
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn run_is_the_default_command() {
+        let command = Cli::parse_from(["yogo", "config.toml", "-p", "2"]).into_command();
+        assert!(matches!(command, Command::Run(args) if args.pages == Some(2)));
+
+        let command = Cli::parse_from(["yogo", "run", "config.toml"]).into_command();
+        assert!(matches!(command, Command::Run(_)));
+    }
+
+    #[test]
+    fn history_commands_parse() {
+        let command = Cli::parse_from(["yogo", "history", "--profile", "work", "forget", "ABCD"])
+            .into_command();
+        let Command::History(args) = command else {
+            panic!("expected history command");
+        };
+        assert_eq!(args.store.profile.as_deref(), Some("work"));
+        assert!(matches!(args.command, HistoryCommand::Forget { infohash } if infohash == "ABCD"));
+    }
+
+    #[test]
+    fn watch_takes_run_options() {
+        let command = Cli::parse_from([
+            "yogo",
+            "watch",
+            "config.toml",
+            "--interval",
+            "30",
+            "-p",
+            "2",
+        ])
+        .into_command();
+        let Command::Watch(args) = command else {
+            panic!("expected watch command");
+        };
+        assert_eq!(args.interval, Some(30));
+        assert_eq!(args.run.pages, Some(2));
+        assert_eq!(args.run.path.as_deref(), Some("config.toml"));
+    }
+
+    #[test]
+    fn dates_are_checked() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 10).unwrap();
+        let parse = |s| ArgDate::parse_relative_to(s, today).map(ArgDate::into_inner);
+
+        assert_eq!(
+            parse("2023-02-28"),
+            NaiveDate::from_ymd_opt(2023, 2, 28).ok_or(ParseArgDateErr::NoSuchDate)
+        );
+        assert_eq!(
+            parse("2023-02-30").unwrap_err(),
+            ParseArgDateErr::NoSuchDate
+        );
+        assert_eq!(
+            parse("2023-13-01").unwrap_err(),
+            ParseArgDateErr::NoSuchDate
+        );
+        assert_eq!(parse("2023-xx-01").unwrap_err(), ParseArgDateErr::Month);
+        assert_eq!(parse("2023").unwrap_err(), ParseArgDateErr::Month);
+        assert!("2023-02-30".parse::<ArgDate>().is_err());
+    }
+
+    #[test]
+    fn dates_can_be_relative() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+        let parse = |s| ArgDate::parse_relative_to(s, today).unwrap().into_inner();
+
+        assert_eq!(parse("today"), today);
+        assert_eq!(
+            parse("yesterday"),
+            NaiveDate::from_ymd_opt(2023, 2, 28).unwrap()
+        );
+        assert_eq!(parse("7d"), NaiveDate::from_ymd_opt(2023, 2, 22).unwrap());
+        assert_eq!(parse("0d"), today);
+        assert!(ArgDate::parse_relative_to("xd", today).is_err());
+    }
+}
@@ -0,0 +1,685 @@
+use std::{
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use unicase::UniCase;
+
+use crate::{
+    args::{start_of_day, ArgDate, Args},
+    filter::{Rejection, Rule, Rules},
+    history::safe_decrement_limit,
+    magnet::Magnet,
+    output::Format,
+    sink::{Delivery, SinkConfig},
+    size::Size,
+    source,
+    tracker::TrackerPolicy,
+};
+
+const DEFAULT_PAGE_LIMIT: usize = 3;
+const DEFAULT_INTERVAL: u64 = 60;
+
+/// Subscription config
+///
+/// Either a TOML file with a `[defaults]` table and one `[[feed]]` table per subscription, or
+/// (for compatibility) a plain list of feed urls, one per line.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default, rename = "feed")]
+    pub feeds: Vec<Feed>,
+    #[serde(default, rename = "sink")]
+    pub sinks: Vec<SinkConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults {
+    pub wait: Option<u64>,
+    pub jobs: Option<usize>,
+    pub pages: Option<usize>,
+    pub output: Option<String>,
+    pub format: Option<Format>,
+    pub min_size: Option<Size>,
+    pub max_size: Option<Size>,
+    pub min_seeders: Option<u32>,
+    #[serde(default)]
+    pub uploaders: Vec<String>,
+    #[serde(default)]
+    pub include: Vec<Rule>,
+    #[serde(default)]
+    pub exclude: Vec<Rule>,
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Keep one magnet per group of near-duplicate titles.
+    pub dedup: Option<bool>,
+    /// Rules in order of preference when picking among near-duplicates.
+    #[serde(default)]
+    pub quality: Vec<Rule>,
+    #[serde(default)]
+    pub trackers: TrackerPolicy,
+    /// Where sinks should tell clients to save downloads.
+    pub save_path: Option<String>,
+    /// Whether sinks should add torrents paused.
+    pub paused: Option<bool>,
+    /// A directory to drop `<infohash>.magnet` files into.
+    pub magnet_dir: Option<PathBuf>,
+    /// Give each feed its own subdirectory of the magnet directory.
+    #[serde(default)]
+    pub magnet_subfolders: bool,
+    /// Minutes between polls of each feed under `watch`.
+    pub interval: Option<u64>,
+    /// The timezone the index prints upload times in.
+    pub timezone: Option<Tz>,
+}
+
+impl Defaults {
+    /// The quality order to dedup by, if dedup is turned on.
+    pub fn dedup<'a>(&'a self, args: &'a Args) -> Option<&'a [Rule]> {
+        (args.dedup || self.dedup.unwrap_or_default())
+            .then(|| or_nonempty(&args.quality, &self.quality))
+    }
+
+    /// The timezone the index prints times in, for anything not tied to one feed.
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Feed {
+    /// Defaults to the feed url.
+    pub name: Option<String>,
+    pub url: String,
+    pub pages: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub after: Option<NaiveDate>,
+    #[serde(default)]
+    pub include: Vec<Rule>,
+    #[serde(default)]
+    pub exclude: Vec<Rule>,
+    pub min_size: Option<Size>,
+    pub max_size: Option<Size>,
+    pub min_seeders: Option<u32>,
+
+    /// Trusted uploaders; when set, uploads by anyone else are dropped.
+    #[serde(default)]
+    pub uploaders: Vec<String>,
+    pub category: Option<String>,
+    pub save_path: Option<String>,
+    pub paused: Option<bool>,
+    pub interval: Option<u64>,
+    pub timezone: Option<Tz>,
+}
+
+impl Feed {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
+    }
+
+    /// Command line options win over feed settings, which win over config defaults.
+    pub fn page_limit(&self, args: &Args, defaults: &Defaults) -> usize {
+        args.pages
+            .or(self.pages)
+            .or(defaults.pages)
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+
+    /// The timezone this feed's index prints times in; UTC unless the config says otherwise.
+    pub fn timezone(&self, defaults: &Defaults) -> Tz {
+        self.timezone.unwrap_or_else(|| defaults.timezone())
+    }
+
+    /// How long `watch` waits between polls of this feed. The command line wins here too.
+    pub fn interval(&self, arg: Option<u64>, defaults: &Defaults) -> Duration {
+        let minutes = arg
+            .or(self.interval)
+            .or(defaults.interval)
+            .unwrap_or(DEFAULT_INTERVAL);
+        Duration::from_secs(minutes * 60)
+    }
+
+    /// How sinks should file magnets from this feed.
+    pub fn delivery<'a>(&'a self, defaults: &'a Defaults) -> Delivery<'a> {
+        Delivery {
+            category: self.category.as_deref(),
+            save_path: self.save_path.as_deref().or(defaults.save_path.as_deref()),
+            paused: self.paused.or(defaults.paused).unwrap_or_default(),
+        }
+    }
+
+    /// The upload time to look back to.
+    ///
+    /// `--date` wins outright. Otherwise a feed which has run before picks up from its last
+    /// successful run (less a day, in case an upload's time lags its appearance), and one that
+    /// hasn't falls back to the default window. A feed's `after` is a floor either way.
+    ///
+    /// Dates begin at midnight in the feed's timezone.
+    pub fn take_after(
+        &self,
+        args: &Args,
+        defaults: &Defaults,
+        last_run: Option<DateTime<Utc>>,
+    ) -> DateTime<Utc> {
+        let tz = self.timezone(defaults);
+        if args.after.is_some() {
+            return args.take_after(tz);
+        }
+
+        let after = self.after.map(|after| start_of_day(after, tz));
+        let since_last_run = last_run.map(safe_decrement_limit);
+        match (after, since_last_run) {
+            (Some(after), Some(since)) => after.max(since),
+            (after, since) => after.or(since).unwrap_or_else(|| args.take_after(tz)),
+        }
+    }
+
+    /// Decides whether a magnet belongs in the listing, and if not, why not.
+    ///
+    /// Title rules are checked at two levels: global rules (from the command line, or failing
+    /// that the config defaults) and the feed's own rules. A magnet has to pass both.
+    pub fn check<'a>(
+        &'a self,
+        magnet: &Magnet,
+        args: &'a Args,
+        defaults: &'a Defaults,
+        last_run: Option<DateTime<Utc>>,
+    ) -> Result<(), Rejection<'a>> {
+        let take_after = self.take_after(args, defaults, last_run);
+        if magnet.date < take_after {
+            return Err(Rejection::Before(take_after));
+        }
+
+        let min_size = args
+            .min_size
+            .as_ref()
+            .or(self.min_size.as_ref())
+            .or(defaults.min_size.as_ref());
+        let max_size = args
+            .max_size
+            .as_ref()
+            .or(self.max_size.as_ref())
+            .or(defaults.max_size.as_ref());
+
+        if let Some(min_size) = min_size.filter(|&min_size| &magnet.size < min_size) {
+            return Err(Rejection::TooSmall(min_size));
+        }
+
+        if let Some(max_size) = max_size.filter(|&max_size| &magnet.size > max_size) {
+            return Err(Rejection::TooLarge(max_size));
+        }
+
+        let min_seeders = args
+            .min_seeders
+            .or(self.min_seeders)
+            .or(defaults.min_seeders);
+        if let Some(min_seeders) = min_seeders.filter(|&min_seeders| magnet.seeders < min_seeders) {
+            return Err(Rejection::TooFewSeeders(min_seeders));
+        }
+
+        let uploaders = or_nonempty(
+            &args.uploaders,
+            or_nonempty(&self.uploaders, &defaults.uploaders),
+        );
+        if !uploaders.is_empty() {
+            let trusted = magnet.uploader.as_ref().is_some_and(|uploader| {
+                uploaders
+                    .iter()
+                    .any(|trusted| UniCase::new(trusted) == UniCase::new(uploader))
+            });
+
+            if !trusted {
+                return Err(Rejection::Untrusted(magnet.uploader.clone()));
+            }
+        }
+
+        let global = Rules {
+            include: or_nonempty(&args.include, &defaults.include),
+            exclude: or_nonempty(&args.exclude, &defaults.exclude),
+        };
+        let feed = Rules {
+            include: &self.include,
+            exclude: &self.exclude,
+        };
+
+        global.check(&magnet.text)?;
+        feed.check(&magnet.text)
+    }
+}
+
+fn or_nonempty<'a, T>(a: &'a [T], b: &'a [T]) -> &'a [T] {
+    if a.is_empty() {
+        b
+    } else {
+        a
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigErr> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        Self::parse(path, &text)
+    }
+
+    /// Reads a config in whichever format it's in.
+    ///
+    /// A `.toml` file is always TOML. Anything else may be either, so TOML is tried first and the
+    /// original one-url-per-line format second.
+    fn parse(path: &Path, text: &str) -> Result<Self, ConfigErr> {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return Self::from_toml(text);
+        }
+
+        match Self::from_toml(text) {
+            Err(ConfigErr::Toml(toml)) => {
+                Self::from_lines(text).map_err(|lines| ConfigErr::Unrecognized {
+                    path: path.into(),
+                    toml,
+                    lines: Box::new(lines),
+                })
+            }
+            result => result,
+        }
+    }
+
+    fn from_toml(text: &str) -> Result<Self, ConfigErr> {
+        let config: Config = toml::from_str(text)?;
+        for feed in &config.feeds {
+            if source::from_url(&feed.url).is_none() {
+                return Err(ConfigErr::UnsupportedFeed {
+                    name: feed.name().into(),
+                    url: feed.url.clone(),
+                });
+            }
+        }
+
+        let mut names: Vec<_> = config
+            .feeds
+            .iter()
+            .map(|feed| UniCase::new(feed.name()))
+            .collect();
+        names.sort_unstable();
+        if let Some(name) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(ConfigErr::DuplicateName(name[0].to_string()));
+        }
+
+        Ok(config)
+    }
+
+    /// Reads the original config format: one url per line.
+    ///
+    /// Blank lines and lines beginning with `#` are ignored.
+    fn from_lines(text: &str) -> Result<Self, ConfigErr> {
+        let mut feeds = Vec::new();
+
+        for (idx, line) in text.lines().enumerate() {
+            let url = line.trim();
+            if url.is_empty() || url.starts_with('#') {
+                continue;
+            }
+
+            if source::from_url(url).is_none() {
+                return Err(ConfigErr::UnsupportedUrl {
+                    line: idx + 1,
+                    url: url.into(),
+                });
+            }
+
+            feeds.push(Feed {
+                url: url.into(),
+                ..Default::default()
+            });
+        }
+
+        Ok(Config {
+            feeds,
+            ..Default::default()
+        })
+    }
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(d: D) -> Result<Option<NaiveDate>, D::Error> {
+    // TOML has a native date type, but a quoted date is just as likely.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DateValue {
+        Date(toml::value::Datetime),
+        Text(String),
+    }
+
+    let text = match DateValue::deserialize(d)? {
+        DateValue::Date(date) => date.to_string(),
+        DateValue::Text(text) => text,
+    };
+
+    text.parse::<ArgDate>()
+        .map(|date| Some(date.into_inner()))
+        .map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug)]
+pub enum ConfigErr {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    UnsupportedUrl {
+        line: usize,
+        url: String,
+    },
+    UnsupportedFeed {
+        name: String,
+        url: String,
+    },
+    DuplicateName(String),
+    /// Neither format could make sense of the file.
+    Unrecognized {
+        path: PathBuf,
+        toml: toml::de::Error,
+        lines: Box<ConfigErr>,
+    },
+}
+
+impl fmt::Display for ConfigErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigErr::Io(e) => write!(f, "Unable to read config: {e}"),
+            ConfigErr::Toml(e) => write!(f, "Bad config: {e}"),
+            ConfigErr::UnsupportedUrl { line, url } => {
+                write!(f, "Unsupported url on line {line}: {url}")
+            }
+            ConfigErr::UnsupportedFeed { name, url } => {
+                write!(f, "Unsupported url for feed {name}: {url}")
+            }
+            ConfigErr::DuplicateName(name) => write!(f, "Duplicate feed name: {name}"),
+            ConfigErr::Unrecognized { path, toml, lines } => write!(
+                f,
+                "Unable to read {} as TOML ({toml}) or as one url per line ({lines})",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigErr {}
+
+impl From<io::Error> for ConfigErr {
+    fn from(e: io::Error) -> Self {
+        ConfigErr::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigErr {
+    fn from(e: toml::de::Error) -> Self {
+        ConfigErr::Toml(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, time::Duration};
+
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::{
+        args::Args,
+        filter::{Rejection, Rule},
+        magnet::Magnet,
+    };
+
+    use super::{Config, ConfigErr, Defaults, Feed};
+
+    #[test]
+    fn can_load_plain_urls() {
+        let config = Config::from_lines(include_str!("../resource/links.txt")).unwrap();
+        assert_eq!(config.feeds.len(), 31);
+        assert_eq!(
+            config.feeds[0].name(),
+            "https://thepiratebay10.org/search/adriana%20chechik/1/3/0"
+        );
+    }
+
+    #[test]
+    fn plain_url_errors_carry_line_number() {
+        let text = "https://thepiratebay10.org/search/a/1/3/0\n\nhttps://example.com/browse/1\n";
+        match Config::from_lines(text) {
+            Err(ConfigErr::UnsupportedUrl { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected unsupported url, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn toml_is_read_whatever_the_file_name() {
+        let text = include_str!("../resource/config.toml");
+        let config = Config::parse(Path::new("yogo.conf"), text).unwrap();
+        assert_eq!(config.feeds.len(), 2);
+
+        let config = Config::parse(Path::new("links"), include_str!("../resource/links.txt"));
+        assert_eq!(config.unwrap().feeds.len(), 31);
+    }
+
+    #[test]
+    fn unreadable_configs_report_both_formats() {
+        let text = "[defaults]\npages = \"three\"\n";
+        let Err(e) = Config::parse(Path::new("yogo.conf"), text) else {
+            panic!("expected an unreadable config");
+        };
+        assert!(matches!(e, ConfigErr::Unrecognized { .. }));
+        let message = e.to_string();
+        assert!(message.starts_with("Unable to read yogo.conf as TOML"));
+        assert!(message.contains("Unsupported url on line 1: [defaults]"));
+    }
+
+    #[test]
+    fn can_load_toml() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        assert_eq!(config.defaults.pages, Some(3));
+        assert_eq!(config.defaults.exclude.len(), 1);
+        assert_eq!(config.defaults.trackers.block.len(), 1);
+        assert_eq!(config.defaults.trackers.add.len(), 1);
+        assert_eq!(config.feeds.len(), 2);
+
+        let feed = &config.feeds[0];
+        assert_eq!(feed.name(), "deen");
+        assert_eq!(feed.pages, Some(1));
+        assert_eq!(
+            feed.after,
+            Some(NaiveDate::from_ymd_opt(2022, 5, 1).unwrap())
+        );
+        assert_eq!(feed.category.as_deref(), Some("scenes"));
+        let delivery = feed.delivery(&config.defaults);
+        assert_eq!(delivery.save_path, Some("/downloads/scenes"));
+        assert!(delivery.paused);
+        assert_eq!(
+            feed.interval(None, &config.defaults),
+            Duration::from_secs(15 * 60)
+        );
+        assert_eq!(
+            config.feeds[1].interval(None, &config.defaults),
+            Duration::from_secs(120 * 60)
+        );
+        assert_eq!(
+            feed.interval(Some(5), &config.defaults),
+            Duration::from_secs(5 * 60)
+        );
+        assert_eq!(config.sinks.len(), 1);
+        assert_eq!(config.sinks[0].name(), "http://localhost:8080");
+        assert_eq!(
+            config.feeds[1].name(),
+            "https://thepiratebay10.org/user/PornBaker/"
+        );
+    }
+
+    #[test]
+    fn cutoff_picks_up_from_last_run() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        let defaults = &config.defaults;
+        let (deen, other) = (&config.feeds[0], &config.feeds[1]);
+        let args = parse_args(&[]);
+        let last_run = Some(Utc.with_ymd_and_hms(2022, 6, 10, 0, 30, 0).unwrap());
+        let slack = Utc.with_ymd_and_hms(2022, 6, 9, 0, 30, 0).unwrap();
+
+        // A day's slack before the last run, but never before the feed's own date.
+        assert_eq!(other.take_after(&args, defaults, last_run), slack);
+        assert_eq!(deen.take_after(&args, defaults, last_run), slack);
+        let floor = Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(deen.take_after(&args, defaults, None), floor);
+        let early = Some(Utc.with_ymd_and_hms(2022, 4, 10, 0, 0, 0).unwrap());
+        assert_eq!(deen.take_after(&args, defaults, early), floor);
+
+        // Without a last run, the default window.
+        let window = other.take_after(&args, defaults, None);
+        assert!(
+            (Utc::now() - window - chrono::Duration::days(3))
+                .num_seconds()
+                .abs()
+                < 5
+        );
+
+        // --date wins over everything.
+        let args = parse_args(&["-d", "2022-01-01"]);
+        let date = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(deen.take_after(&args, defaults, last_run), date);
+        assert_eq!(other.take_after(&args, defaults, last_run), date);
+    }
+
+    #[test]
+    fn dates_begin_in_the_feed_timezone() {
+        let mut config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        config.feeds[0].timezone = Some(chrono_tz::Europe::Stockholm);
+        let deen = &config.feeds[0];
+
+        assert_eq!(
+            deen.timezone(&config.defaults),
+            chrono_tz::Europe::Stockholm
+        );
+        assert_eq!(config.feeds[1].timezone(&config.defaults), chrono_tz::UTC);
+        assert_eq!(
+            deen.take_after(&parse_args(&[]), &config.defaults, None),
+            Utc.with_ymd_and_hms(2022, 4, 30, 22, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn dedup_is_opt_in() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        let args = parse_args(&[]);
+        assert_eq!(config.defaults.dedup(&args).unwrap().len(), 3);
+        let args = parse_args(&["-q", "4k"]);
+        assert_eq!(config.defaults.dedup(&args).unwrap().len(), 1);
+
+        let defaults = Defaults::default();
+        assert!(defaults.dedup(&parse_args(&[])).is_none());
+        assert!(defaults.dedup(&parse_args(&["--dedup"])).is_some());
+    }
+
+    #[test]
+    fn title_rules_layer() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        let defaults = &config.defaults;
+        let args = parse_args(&[]);
+
+        let feed = &config.feeds[0];
+        assert!(check(feed, "James Deen 1080P", "1 GiB", &args, defaults).is_ok());
+        assert!(matches!(
+            check(feed, "James Deen 720p", "1 GiB", &args, defaults),
+            Err(Rejection::NotIncluded(_))
+        ));
+        assert!(matches!(
+            check(feed, "James Deen 1080p sample", "1 GiB", &args, defaults),
+            Err(Rejection::Excluded(_))
+        ));
+
+        let feed = &config.feeds[1];
+        assert!(check(feed, "anything", "1 GiB", &args, defaults).is_ok());
+        assert!(matches!(
+            check(feed, "anything 480p", "1 GiB", &args, defaults),
+            Err(Rejection::Excluded(_))
+        ));
+
+        // Rules given on the command line replace the global rules in the config.
+        let args = parse_args(&["--exclude", "anything"]);
+        assert!(matches!(
+            check(feed, "anything 480p", "1 GiB", &args, defaults),
+            Err(Rejection::Excluded(Rule::Keyword(keyword))) if keyword == "anything"
+        ));
+    }
+
+    #[test]
+    fn size_limits_layer() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        let defaults = &config.defaults;
+        let args = parse_args(&[]);
+        let feed = &config.feeds[0];
+
+        assert!(matches!(
+            check(feed, "1080p", "100 MiB", &args, defaults),
+            Err(Rejection::TooSmall(_))
+        ));
+        assert!(check(feed, "1080p", "1 GiB", &args, defaults).is_ok());
+        assert!(matches!(
+            check(feed, "1080p", "9 GiB", &args, defaults),
+            Err(Rejection::TooLarge(_))
+        ));
+        assert!(check(&config.feeds[1], "1080p", "100 MiB", &args, defaults).is_ok());
+
+        let args = parse_args(&["--min-size", "2GiB"]);
+        assert!(matches!(
+            check(feed, "1080p", "1 GiB", &args, defaults),
+            Err(Rejection::TooSmall(_))
+        ));
+    }
+
+    #[test]
+    fn seeder_and_uploader_limits() {
+        let config = Config::from_toml(include_str!("../resource/config.toml")).unwrap();
+        let defaults = &config.defaults;
+        let feed = &config.feeds[1];
+
+        let args = parse_args(&["--min-seeders", "20"]);
+        assert!(matches!(
+            check(feed, "1080p", "1 GiB", &args, defaults),
+            Err(Rejection::TooFewSeeders(20))
+        ));
+
+        let args = parse_args(&["--uploader", "someone"]);
+        assert!(matches!(
+            check(feed, "1080p", "1 GiB", &args, defaults),
+            Err(Rejection::Untrusted(Some(_)))
+        ));
+
+        let args = parse_args(&["--uploader", "someone", "--uploader", "pornbaker"]);
+        assert!(check(feed, "1080p", "1 GiB", &args, defaults).is_ok());
+    }
+
+    fn check<'a>(
+        feed: &'a Feed,
+        text: &str,
+        size: &str,
+        args: &'a Args,
+        defaults: &'a Defaults,
+    ) -> Result<(), Rejection<'a>> {
+        let magnet = Magnet {
+            text: text.into(),
+            size: size.parse().unwrap(),
+            date: Utc::now(),
+            seeders: 10,
+            uploader: Some("PornBaker".into()),
+            ..Magnet::sample()
+        };
+
+        feed.check(&magnet, args, defaults, None)
+    }
+
+    fn parse_args(args: &[&str]) -> Args {
+        let args = ["yogo", "config.toml"].iter().chain(args);
+        <Args as clap::Parser>::parse_from(args)
+    }
+}
@@ -1,177 +1,350 @@
-use hashbrown::HashSet;
-use regex::Regex;
+use std::{
+    env, error, fmt, io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+    thread,
+};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use reqwest::blocking::Client;
-use scraper::{ElementRef, Html, Selector};
+use scraper::{Html, Selector};
 
 use crate::{
-    magnet::{ExtractMagnetContextErr, Magnet, MagnetContext},
-    wait::Waiter,
+    config::Feed,
+    magnet::Magnet,
+    mirror::{self, Mirrors},
+    recording::{self, Recording},
+    source::{self, Source, Unrecognized},
+    wait::HostWaiter,
 };
 
 static USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:105.0) Gecko/20100101 Firefox/105.0";
 
-trait LinkBuilder {
-    fn link(&self, page: usize) -> String;
+pub struct Context {
+    client: Client,
+    mirrors: Mirrors,
+    waiter: HostWaiter,
+    recording: Recording,
 }
 
-struct SearchLinkBuilder {
-    query: String,
-}
+/// Magnets taken from a single feed
+pub struct Recent {
+    pub magnets: Vec<Magnet>,
 
-impl LinkBuilder for SearchLinkBuilder {
-    fn link(&self, page: usize) -> String {
-        // https://thepiratebay10.org/search/James%20Deen/1/3/0
+    /// The base url which served the last page of the feed.
+    pub mirror: String,
 
-        // The path segment "3" refers to sorting by upload date.
-        // The last path segment refers to the search category.
+    /// The number of result rows which could not be read and were skipped.
+    pub skipped: usize,
 
-        let query = &self.query;
-        format!("https://thepiratebay10.org/search/{query}/{page}/3/0")
-    }
-}
+    /// The number of pages actually fetched, which may be fewer than the page limit.
+    pub pages: usize,
 
-struct UserLinkBuilder {
-    user: String,
+    /// Whether paging got as far as something stale. If not, it stopped at the page limit with
+    /// everything still new, and there may be more beyond it.
+    pub caught_up: bool,
 }
 
-impl LinkBuilder for UserLinkBuilder {
-    fn link(&self, page: usize) -> String {
-        // https://thepiratebay10.org/user/PornBaker/2/3
-
-        // Note the absence of the search category from above.
-
-        let user = &self.user;
-        format!("https://thepiratebay10.org/user/{user}/{page}/3")
+impl Context {
+    pub fn new(mirrors: Mirrors, waiter: HostWaiter, recording: Recording) -> Self {
+        Context {
+            client: build_client(),
+            mirrors,
+            waiter,
+            recording,
+        }
     }
-}
 
-fn link_builder(url: &str) -> Option<Box<dyn LinkBuilder>> {
-    let search = Regex::new(r#"/search/([^/]+)"#).unwrap();
-    if let Some(cx) = search.captures(url) {
-        return cx.get(1).map(|cx| {
-            Box::new(SearchLinkBuilder {
-                query: cx.as_str().into(),
-            }) as Box<dyn LinkBuilder>
-        });
-    }
+    /// Extracts recent magnets from each feed using a pool of `jobs` workers.
+    ///
+    /// Results are returned in the same order as the feeds, however the work was scheduled.
+    pub fn extract_all(
+        &self,
+        feeds: &[&Feed],
+        limit: impl Fn(&Feed) -> usize + Sync,
+        timezone: impl Fn(&Feed) -> Tz + Sync,
+        is_stale: impl Fn(&Feed, &Magnet) -> bool + Sync,
+        jobs: usize,
+    ) -> Vec<anyhow::Result<Recent>> {
+        let next = AtomicUsize::new(0);
+        let worker = || {
+            let mut results = Vec::new();
+            loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&feed) = feeds.get(idx) else {
+                    return results;
+                };
+                let is_stale = |magnet: &Magnet| is_stale(feed, magnet);
+                let recent = self.extract_recent(feed, limit(feed), timezone(feed), is_stale);
+                results.push((idx, recent));
+            }
+        };
 
-    let user = Regex::new(r#"/user/([^/]+)"#).unwrap();
-    if let Some(cx) = user.captures(url) {
-        return cx.get(1).map(|cx| {
-            Box::new(UserLinkBuilder {
-                user: cx.as_str().into(),
-            }) as Box<dyn LinkBuilder>
+        let mut results: Vec<_> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.clamp(1, feeds.len().max(1)))
+                .map(|_| scope.spawn(worker))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("worker panicked"))
+                .collect()
         });
-    }
 
-    None
-}
-
-pub struct Context {
-    client: Client,
-    det_selector: Selector,
-    page_link_selector: Selector,
-    magnet_link_selector: Selector,
-    info_selector: Selector,
-    size_pattern: Regex,
-}
-
-impl Context {
-    pub fn new() -> Self {
-        Context {
-            client: build_client(),
-            det_selector: Selector::parse("td > div.detName").unwrap(),
-            page_link_selector: Selector::parse("div.detName > a").unwrap(),
-            magnet_link_selector: Selector::parse("div.detName + a").unwrap(),
-            info_selector: Selector::parse("font").unwrap(),
-            size_pattern: Regex::new(r#"Size ([\d.]+)&nbsp;([^,]+)"#).unwrap(),
-        }
+        results.sort_by_key(|&(idx, _)| idx);
+        results.into_iter().map(|(_, result)| result).collect()
     }
 
+    /// Extracts magnets from up to `limit` pages of a feed.
+    ///
+    /// Feeds are sorted newest first, so once every magnet on a page is stale (too old, or
+    /// already seen) the pages after it won't have anything for us either.
     pub fn extract_recent(
         &self,
-        url: &str,
+        feed: &Feed,
         limit: usize,
-        filter: &mut HashSet<String>,
-        waiter: &mut Waiter,
-    ) -> anyhow::Result<Vec<Magnet>> {
+        tz: Tz,
+        is_stale: impl Fn(&Magnet) -> bool,
+    ) -> anyhow::Result<Recent> {
         // We need to begin pagination with 1 or there's going to be weirdness.
         let pages = 1..=limit;
-        let links = link_builder(url).ok_or_else(|| {
+        let url = &feed.url;
+        let source = source::from_url(url).ok_or_else(|| {
             std::io::Error::new(
                 std::io::ErrorKind::Unsupported,
                 format!("unsupported url: {url}"),
             )
         })?;
 
-        let page_links = pages.map(|page| links.link(page));
-
         let mut magnets = Vec::new();
+        let mut mirror = source.origin().to_string();
+        let mut skipped = 0;
+        let mut fetched = 0;
+        let mut caught_up = false;
+
+        for page in pages {
+            let path = source.path(page);
+            let (text, served_by) = self.fetch(&path, source.origin())?;
+            fetched = page;
 
-        for url in page_links {
-            waiter.wait();
-
-            let text = self.client.get(url).send()?.text()?;
-            let document = Html::parse_fragment(&text);
-            let det_elements = document
-                .select(&self.det_selector)
-                .filter_map(|element| ElementRef::wrap(element.parent()?));
-
-            for element in det_elements {
-                let link = self.get_magnet_link(&element)?;
-                if filter.insert(link.to_string()) {
-                    let info = self.get_info(&element)?;
-                    let size = self
-                        .size_pattern
-                        .captures(&info)
-                        .ok_or_else(|| ExtractMagnetContextErr::Size(info.to_string()))?;
-
-                    let magnet_context = MagnetContext {
-                        text: self.get_link_text(&element)?,
-                        link: self.get_magnet_link(&element)?,
-                        size: format!(
-                            "{} {}",
-                            size.get(1).unwrap().as_str(),
-                            size.get(2).unwrap().as_str()
-                        ),
-                        info: self.get_info(&element)?,
-                    };
-
-                    magnets.push(magnet_context.try_into()?);
+            // A single bad row shouldn't cost us the rest of the page, so rows that can't be
+            // read are logged and skipped.
+            let read = match read_page(&text, &*source, feed, tz, Utc::now()) {
+                Ok(read) => read,
+                Err(reason) => {
+                    let url = format!("{served_by}{path}");
+                    return Err(LayoutErr {
+                        feed: feed.name().into(),
+                        reason,
+                        page: self.keep_page(&url, &text)?,
+                    }
+                    .into());
                 }
+            };
+            mirror = served_by;
+            for e in &read.skipped {
+                eprintln!("{}: skipping row: {e}", feed.name());
+            }
+            skipped += read.skipped.len();
+            let has_rows = read.rows > 0;
+            let first = magnets.len();
+            magnets.extend(read.magnets);
+
+            caught_up = !has_rows || magnets[first..].iter().any(&is_stale);
+            if is_last_page(has_rows, &magnets[first..], &is_stale) {
+                break;
             }
         }
 
-        Ok(magnets)
+        Ok(Recent {
+            magnets,
+            mirror,
+            skipped,
+            pages: fetched,
+            caught_up,
+        })
     }
 
-    fn get_link_text(&self, element: &ElementRef) -> Result<String, ExtractMagnetContextErr> {
-        let link_element = element
-            .select(&self.page_link_selector)
-            .next()
-            .ok_or_else(|| ExtractMagnetContextErr::PageLink(element.html()))?;
-        Ok(link_element.inner_html())
+    /// Where to find a page that couldn't be read. Unless it was already recorded, it's saved to
+    /// the temp dir.
+    fn keep_page(&self, url: &str, text: &str) -> io::Result<PathBuf> {
+        match &self.recording {
+            Recording::Record(dir) | Recording::Replay(dir) => Ok(recording::page_path(dir, url)),
+            Recording::Off => recording::save(&env::temp_dir().join("yogo-pages"), url, text),
+        }
     }
 
-    fn get_magnet_link<'a>(
-        &self,
-        element: &'a ElementRef,
-    ) -> Result<&'a str, ExtractMagnetContextErr> {
-        let link_element = element
-            .select(&self.magnet_link_selector)
-            .next()
-            .and_then(|element| element.value().attr("href"))
-            .ok_or_else(|| ExtractMagnetContextErr::MagnetLink(element.html()))?;
-        Ok(link_element)
-    }
-
-    fn get_info(&self, element: &ElementRef) -> Result<String, ExtractMagnetContextErr> {
-        let info_element = element
-            .select(&self.info_selector)
-            .next()
-            .ok_or_else(|| ExtractMagnetContextErr::Info(element.html()))?;
-        Ok(info_element.inner_html())
+    /// Requests a path from each candidate mirror in turn, returning the page and the mirror
+    /// that served it.
+    ///
+    /// When replaying, the first mirror with a recorded copy of the page serves it. A 4xx is an
+    /// error rather than a page, since it says nothing about the site's markup. A challenge
+    /// served with a 5xx still counts as a failure, but if no mirror does better it's returned
+    /// so that it can be reported as a challenge.
+    fn fetch(&self, path: &str, origin: &str) -> anyhow::Result<(String, String)> {
+        let mut failures = Vec::new();
+        let mut challenge = None;
+
+        for base in self.mirrors.candidates(origin) {
+            let url = format!("{base}{path}");
+            if let Recording::Replay(dir) = &self.recording {
+                match recording::load(dir, &url)? {
+                    Some(text) => return Ok((text, base)),
+                    None => {
+                        failures.push(format!("{base}: not recorded"));
+                        continue;
+                    }
+                }
+            }
+
+            self.waiter.wait(&base);
+
+            let result = self.client.get(&url).send();
+            if let Some(reason) = mirror::should_fail_over(&result) {
+                self.mirrors.mark_failed(&base);
+                failures.push(format!("{base}: {reason}"));
+                if let Ok(response) = result {
+                    let text = response.text().unwrap_or_default();
+                    if challenge.is_none() && is_challenge(&Html::parse_document(&text)) {
+                        challenge = Some((url, text, base));
+                    }
+                }
+                continue;
+            }
+
+            let text = result?.error_for_status()?.text()?;
+            self.mirrors.mark_healthy(&base);
+            if let Recording::Record(dir) = &self.recording {
+                recording::save(dir, &url, &text)?;
+            }
+            return Ok((text, base));
+        }
+
+        if let Some((url, text, base)) = challenge {
+            if let Recording::Record(dir) = &self.recording {
+                recording::save(dir, &url, &text)?;
+            }
+            return Ok((text, base));
+        }
+        anyhow::bail!("all mirrors failed for {path}:\n{}", failures.join("\n"))
+    }
+}
+
+/// What a single results page held
+pub struct Page {
+    pub magnets: Vec<Magnet>,
+
+    /// The number of result rows, whether or not they could be read.
+    pub rows: usize,
+
+    /// Why each unreadable row was skipped.
+    pub skipped: Vec<String>,
+}
+
+/// A page which came back fine, but isn't anything we know how to read
+///
+/// Usually this means the site has changed its markup, or put a captcha in front of it. Either
+/// way it's not the same as a feed with nothing new.
+#[derive(Debug)]
+pub struct LayoutErr {
+    pub feed: String,
+    pub reason: Unrecognized,
+    /// Where the page was saved, for a closer look or a bug report.
+    pub page: PathBuf,
+}
+
+impl fmt::Display for LayoutErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "layout not recognized for {}: {} (page saved to {})",
+            self.feed,
+            self.reason,
+            self.page.display()
+        )
+    }
+}
+
+impl error::Error for LayoutErr {}
+
+/// Reads the magnets from the text of a results page, without going anywhere near the network.
+pub fn read_page(
+    text: &str,
+    source: &dyn Source,
+    feed: &Feed,
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<Page, Unrecognized> {
+    let document = Html::parse_fragment(text);
+    if is_challenge(&document) {
+        return Err(Unrecognized::Challenge);
+    }
+    if !source.is_results_page(&document) {
+        return Err(Unrecognized::NoResults);
+    }
+
+    let rows = source.extract(&document);
+    let mut page = Page {
+        magnets: Vec::new(),
+        rows: rows.len(),
+        skipped: Vec::new(),
+    };
+
+    for magnet_context in rows {
+        let magnet = magnet_context.map_err(|e| e.to_string()).and_then(|cx| {
+            Magnet::from_context(cx, source, feed, tz, now).map_err(|e| e.to_string())
+        });
+        match magnet {
+            Ok(magnet) => page.magnets.push(magnet),
+            Err(e) => page.skipped.push(e),
+        }
+    }
+
+    if page.rows > 0 && page.magnets.is_empty() {
+        return Err(Unrecognized::UnreadableRows);
     }
+    Ok(page)
+}
+
+/// Captchas and "checking your browser" pages, which come back with a 200 like anything else.
+fn is_challenge(document: &Html) -> bool {
+    static TITLES: &[&str] = &[
+        "just a moment",
+        "attention required",
+        "ddos-guard",
+        "captcha",
+    ];
+    static SELECTORS: OnceLock<(Selector, Selector)> = OnceLock::new();
+    let (title, markers) = SELECTORS.get_or_init(|| {
+        let markers = [
+            "#challenge-form",
+            "#cf-wrapper",
+            ".cf-browser-verification",
+            ".g-recaptcha",
+            ".h-captcha",
+            "iframe[src*=captcha]",
+        ];
+        (
+            Selector::parse("title").unwrap(),
+            Selector::parse(&markers.join(", ")).unwrap(),
+        )
+    });
+
+    let title = document
+        .select(title)
+        .next()
+        .map(|title| title.text().collect::<String>().to_lowercase())
+        .unwrap_or_default();
+
+    TITLES.iter().any(|marker| title.contains(marker)) || document.select(markers).next().is_some()
+}
+
+/// An empty page means we've run out of results.
+fn is_last_page(has_rows: bool, magnets: &[Magnet], is_stale: impl Fn(&Magnet) -> bool) -> bool {
+    !has_rows || magnets.iter().all(is_stale)
 }
 
 fn build_client() -> Client {
@@ -183,8 +356,275 @@ fn build_client() -> Client {
 
 #[cfg(test)]
 mod tests {
+    use std::{env, fmt::Write, fs, path::Path};
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{Context, LayoutErr, Page};
+    use crate::{
+        config::Feed,
+        magnet::Magnet,
+        mirror::Mirrors,
+        recording::{self, Recording},
+        sink::mock,
+        source::{self, Unrecognized},
+        testing::TempDir,
+        wait::{HostWaiter, Waiter},
+    };
+
+    static SEARCH: &str = include_str!("../resource/fixtures/tpb/search.html");
+
+    /// Set to rewrite the `.snap` files from what the parser currently produces.
+    const UPDATE_SNAPSHOTS: &str = "YOGO_UPDATE_SNAPSHOTS";
+
+    /// Reads a fixture page as if it came from `url` at noon on 10 June 2022, and checks the
+    /// result against the fixture's snapshot.
+    fn assert_page_snapshot(fixture: &str, url: &str) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resource/fixtures/tpb");
+        let text = fs::read_to_string(dir.join(format!("{fixture}.html"))).unwrap();
+        let source = source::from_url(url).unwrap();
+        let feed = Feed {
+            url: url.into(),
+            ..Default::default()
+        };
+        let now = Utc.with_ymd_and_hms(2022, 6, 10, 12, 0, 0).unwrap();
+        let actual = match super::read_page(&text, &*source, &feed, chrono_tz::UTC, now) {
+            Ok(page) => render(&page),
+            Err(reason) => format!("layout not recognized: {reason}\n"),
+        };
+        let path = dir.join(format!("{fixture}.snap"));
+        if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+            fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            actual == expected,
+            "{fixture}: parsed page doesn't match {}; rerun with {UPDATE_SNAPSHOTS}=1 to \
+             accept it\n--- expected\n{expected}\n--- actual\n{actual}",
+            path.display(),
+        );
+    }
+
+    fn render(page: &Page) -> String {
+        let mut buf = format!("{} rows, {} skipped\n", page.rows, page.skipped.len());
+        for magnet in &page.magnets {
+            writeln!(buf, "\n{}", magnet.text).unwrap();
+            writeln!(buf, "  uploaded  {}", magnet.date.format("%F %R")).unwrap();
+            writeln!(buf, "  infohash  {}", magnet.infohash().unwrap_or_default()).unwrap();
+            writeln!(buf, "  size      {}", magnet.size).unwrap();
+            writeln!(buf, "  peers     {} / {}", magnet.seeders, magnet.leechers).unwrap();
+            let uploader = magnet.uploader.as_deref().unwrap_or("(anonymous)");
+            writeln!(buf, "  uploader  {uploader}").unwrap();
+            writeln!(buf, "  kind      {}", magnet.kind.as_deref().unwrap_or("")).unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn search_page() {
+        assert_page_snapshot("search", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn user_page() {
+        assert_page_snapshot("user", "https://tpb.example/user/PornBaker/");
+    }
+
+    #[test]
+    fn empty_results() {
+        assert_page_snapshot("empty", "https://tpb.example/search/nothing%20here/1/3/0");
+    }
+
+    #[test]
+    fn vip_and_trusted_icons() {
+        assert_page_snapshot("icons", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn today_and_yesterday_dates() {
+        assert_page_snapshot("relative-dates", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn missing_size() {
+        assert_page_snapshot("missing-size", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn captcha_is_not_a_results_page() {
+        assert_page_snapshot("captcha", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn redesign_is_not_a_results_page() {
+        assert_page_snapshot("redesigned", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn rows_with_new_markup_are_not_results() {
+        assert_page_snapshot("unreadable-rows", "https://tpb.example/search/sample/1/3/0");
+    }
+
+    #[test]
+    fn unrecognized_pages_are_reported_with_their_path() {
+        let dir = TempDir::new("layout");
+        let url = "https://tpb.example/search/sample/1/3/0";
+        let captcha = include_str!("../resource/fixtures/tpb/captcha.html");
+        recording::save(&dir, url, captcha).unwrap();
+
+        let context = Context::new(
+            Mirrors::default(),
+            HostWaiter::new(Waiter::with_wait(0)),
+            Recording::Replay(dir.to_path_buf()),
+        );
+        let feed = Feed {
+            name: Some("sample".into()),
+            url: url.into(),
+            ..Default::default()
+        };
+
+        let Err(e) = context.extract_recent(&feed, 1, chrono_tz::UTC, |_| false) else {
+            panic!("expected a layout error");
+        };
+        let e = e.downcast::<LayoutErr>().unwrap();
+        assert_eq!(e.reason, Unrecognized::Challenge);
+        assert_eq!(e.page, recording::page_path(&dir, url));
+        assert!(e
+            .to_string()
+            .starts_with("layout not recognized for sample: "));
+    }
+
+    #[test]
+    fn http_errors_are_not_layout_errors() {
+        let (base, _requests) = mock::serve(vec![
+            tiny_http::Response::from_string("not found").with_status_code(404)
+        ]);
+        let context = Context::new(
+            Mirrors::default(),
+            HostWaiter::new(Waiter::with_wait(0)),
+            Recording::Off,
+        );
+        let feed = Feed {
+            url: format!("{base}/search/sample/1/3/0"),
+            ..Default::default()
+        };
+
+        let Err(e) = context.extract_recent(&feed, 1, chrono_tz::UTC, |_| false) else {
+            panic!("expected an HTTP error");
+        };
+        assert!(e.downcast_ref::<LayoutErr>().is_none());
+        assert!(e.to_string().contains("404"));
+    }
+
+    #[test]
+    fn challenges_served_as_unavailable_are_reported() {
+        let captcha = include_str!("../resource/fixtures/tpb/captcha.html");
+        let (base, _requests) = mock::serve(vec![
+            tiny_http::Response::from_string(captcha).with_status_code(503)
+        ]);
+        let dir = TempDir::new("challenge-503");
+        let context = Context::new(
+            Mirrors::default(),
+            HostWaiter::new(Waiter::with_wait(0)),
+            Recording::Record(dir.to_path_buf()),
+        );
+        let url = format!("{base}/search/sample/1/3/0");
+        let feed = Feed {
+            url: url.clone(),
+            ..Default::default()
+        };
+
+        let Err(e) = context.extract_recent(&feed, 1, chrono_tz::UTC, |_| false) else {
+            panic!("expected a layout error");
+        };
+        let e = e.downcast::<LayoutErr>().unwrap();
+        assert_eq!(e.reason, Unrecognized::Challenge);
+        assert_eq!(e.page, recording::page_path(&dir, &url));
+        assert!(e.page.exists());
+    }
+
     #[test]
     fn build_client() {
         super::build_client();
     }
+
+    #[test]
+    fn replays_recorded_pages() {
+        let dir = TempDir::new("replay");
+        recording::save(&dir, "https://tpb.example/search/sample/1/3/0", SEARCH).unwrap();
+
+        let context = Context::new(
+            Mirrors::new(["https://dead.example", "https://tpb.example"]),
+            HostWaiter::new(Waiter::with_wait(0)),
+            Recording::Replay(dir.to_path_buf()),
+        );
+        let feed = Feed {
+            url: "https://tpb.example/search/sample/1/3/0".into(),
+            ..Default::default()
+        };
+
+        let recent = context
+            .extract_recent(&feed, 1, chrono_tz::UTC, |_| false)
+            .unwrap();
+        assert_eq!(recent.magnets.len(), 3);
+        assert_eq!(recent.mirror, "https://tpb.example");
+
+        // The second page was never recorded, so paging on fails there.
+        let Err(e) = context.extract_recent(&feed, 2, chrono_tz::UTC, |_| false) else {
+            panic!("expected a missing page");
+        };
+        assert!(e.to_string().contains("https://tpb.example: not recorded"));
+    }
+
+    #[test]
+    fn stopping_at_the_page_limit_is_not_caught_up() {
+        let dir = TempDir::new("limit");
+        let url = "https://tpb.example/search/sample/1/3/0";
+        recording::save(&dir, url, SEARCH).unwrap();
+
+        let context = Context::new(
+            Mirrors::default(),
+            HostWaiter::new(Waiter::with_wait(0)),
+            Recording::Replay(dir.to_path_buf()),
+        );
+        let feed = Feed {
+            url: url.into(),
+            ..Default::default()
+        };
+
+        // Every result on the only page allowed was new, so there may be more after it.
+        let recent = context
+            .extract_recent(&feed, 1, chrono_tz::UTC, |_| false)
+            .unwrap();
+        assert!(!recent.caught_up);
+
+        let recent = context
+            .extract_recent(&feed, 1, chrono_tz::UTC, |magnet| magnet.seeders < 10)
+            .unwrap();
+        assert!(recent.caught_up);
+    }
+
+    #[test]
+    fn stops_paging_once_everything_is_stale() {
+        let cutoff = Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap();
+        let is_stale = |magnet: &Magnet| magnet.date < cutoff;
+        let old = magnet(Utc.with_ymd_and_hms(2022, 4, 30, 23, 0, 0).unwrap());
+        let new = magnet(Utc.with_ymd_and_hms(2022, 5, 2, 0, 0, 0).unwrap());
+
+        assert!(super::is_last_page(
+            true,
+            &[old.clone(), old.clone()],
+            is_stale
+        ));
+        assert!(!super::is_last_page(true, &[new, old], is_stale));
+        assert!(super::is_last_page(false, &[], is_stale));
+    }
+
+    fn magnet(date: DateTime<Utc>) -> Magnet {
+        Magnet {
+            date,
+            ..Magnet::sample()
+        }
+    }
 }
@@ -0,0 +1,143 @@
+use hashbrown::HashMap;
+use regex::Regex;
+
+use crate::{filter::Rule, magnet::Magnet};
+
+/// Fuzzy duplicate detection by title
+///
+/// Titles are compared after dropping resolution, codec and container tokens and punctuation,
+/// so "Some.Release.1080p.x264.mp4" and "Some Release [720p]" land in the same group. Within a
+/// group, the first quality rule to match wins; ties go to the magnet with more seeders, and
+/// then to whichever came first.
+#[derive(Clone, Debug)]
+pub struct Dedup<'a> {
+    quality: &'a [Rule],
+    tokens: Regex,
+    punctuation: Regex,
+}
+
+impl<'a> Dedup<'a> {
+    pub fn new(quality: &'a [Rule]) -> Self {
+        Self {
+            quality,
+            tokens: Regex::new(concat!(
+                r"\b(",
+                r"\d{3,4}[pi]|[248]k|uhd|fhd|hd|sd|",
+                r"[hx]\.?26[45]|hevc|avc|xvid|divx|av1|vp9|10bit|",
+                r"mp4|mkv|avi|wmv|mov|m4v|webm",
+                r")\b",
+            ))
+            .unwrap(),
+            punctuation: Regex::new(r"[^\p{L}\p{N}]+").unwrap(),
+        }
+    }
+
+    /// The text magnets are grouped by.
+    pub fn key(&self, text: &str) -> String {
+        let text = text.to_lowercase();
+        let text = self.tokens.replace_all(&text, " ");
+        let text = self.punctuation.replace_all(&text, " ");
+        text.trim().to_owned()
+    }
+
+    /// Keeps the best magnet from each group, in the position of the group's first magnet.
+    ///
+    /// Returns the magnets dropped, each with the title of the magnet kept in its place.
+    pub fn dedup(&self, magnets: &mut Vec<Magnet>) -> Vec<(Magnet, String)> {
+        let mut groups = HashMap::new();
+        let mut kept: Vec<Magnet> = Vec::with_capacity(magnets.len());
+        let mut dropped = Vec::new();
+
+        for magnet in magnets.drain(..) {
+            let key = self.key(&magnet.text);
+            if key.is_empty() {
+                kept.push(magnet);
+                continue;
+            }
+
+            match groups.get(&key) {
+                None => {
+                    groups.insert(key, kept.len());
+                    kept.push(magnet);
+                }
+                Some(&idx) if self.rank(&magnet) < self.rank(&kept[idx]) => {
+                    dropped.push((std::mem::replace(&mut kept[idx], magnet), idx));
+                }
+                Some(&idx) => dropped.push((magnet, idx)),
+            }
+        }
+
+        let dropped = dropped
+            .into_iter()
+            .map(|(magnet, idx)| (magnet, kept[idx].text.clone()))
+            .collect();
+        *magnets = kept;
+        dropped
+    }
+
+    /// Lower is better.
+    fn rank(&self, magnet: &Magnet) -> (usize, std::cmp::Reverse<u32>) {
+        let quality = self
+            .quality
+            .iter()
+            .position(|rule| rule.is_match(&magnet.text))
+            .unwrap_or(self.quality.len());
+        (quality, std::cmp::Reverse(magnet.seeders))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dedup;
+    use crate::{filter::Rule, magnet::Magnet};
+
+    fn magnet(text: &str, seeders: u32) -> Magnet {
+        Magnet {
+            text: text.into(),
+            link: format!("magnet:?xt=urn:btih:{text}"),
+            seeders,
+            ..Magnet::sample()
+        }
+    }
+
+    #[test]
+    fn keys_ignore_quality_tokens_and_punctuation() {
+        let dedup = Dedup::new(&[]);
+        let expected = "some release part 2";
+        for text in [
+            "Some.Release.Part.2.1080p.x264.mp4",
+            "Some Release - Part 2 [720p] (H.265)",
+            "SOME RELEASE PART 2 4K HEVC MKV",
+        ] {
+            assert_eq!(dedup.key(text), expected, "{text}");
+        }
+
+        // Numbers which aren't resolutions are part of the title.
+        assert_ne!(dedup.key("Some Release 1080"), dedup.key("Some Release"));
+    }
+
+    #[test]
+    fn prefers_quality_order_then_seeders() {
+        let quality: Vec<Rule> = ["2160p", "1080p"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        let dedup = Dedup::new(&quality);
+
+        let mut magnets = vec![
+            magnet("Other Thing 720p", 50),
+            magnet("Some Release 720p", 50),
+            magnet("Some Release 1080p", 5),
+            magnet("Some.Release.1080p", 9),
+            magnet("Some Release 480p", 100),
+        ];
+        let dropped = dedup.dedup(&mut magnets);
+
+        let kept: Vec<_> = magnets.iter().map(|magnet| magnet.text.as_str()).collect();
+        assert_eq!(kept, ["Other Thing 720p", "Some.Release.1080p"]);
+        assert_eq!(dropped.len(), 3);
+        assert!(dropped
+            .iter()
+            .all(|(_, winner)| winner == "Some.Release.1080p"));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
+
+use crate::size::Size;
+
+/// A title filter
+///
+/// Rules are written either as a plain keyword, which matches anywhere in a title without
+/// regard to case, or as a regex between slashes, e.g. `/\b(480|720)p\b/`. A trailing `i`
+/// (`/sample/i`) makes the regex case-insensitive.
+#[derive(Clone, Debug)]
+pub enum Rule {
+    Keyword(String),
+    Pattern(Regex),
+}
+
+impl Rule {
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Rule::Keyword(keyword) => text.to_lowercase().contains(keyword),
+            Rule::Pattern(pattern) => pattern.is_match(text),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = regex::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s
+            .strip_prefix('/')
+            .and_then(|s| s.strip_suffix('/').map(|s| (s, false)))
+            .or_else(|| {
+                s.strip_prefix('/')
+                    .and_then(|s| s.strip_suffix("/i").map(|s| (s, true)))
+            });
+
+        match pattern {
+            Some((pattern, case_insensitive)) => RegexBuilder::new(pattern)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map(Rule::Pattern),
+            None => Ok(Rule::Keyword(s.to_lowercase())),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Keyword(keyword) => write!(f, "keyword {keyword:?}"),
+            Rule::Pattern(pattern) => write!(f, "regex /{pattern}/"),
+        }
+    }
+}
+
+/// Include and exclude rules at one level (global or per feed)
+///
+/// A title must match at least one include rule, if there are any, and no exclude rules.
+#[derive(Clone, Copy, Debug)]
+pub struct Rules<'a> {
+    pub include: &'a [Rule],
+    pub exclude: &'a [Rule],
+}
+
+impl<'a> Rules<'a> {
+    pub fn check(&self, text: &str) -> Result<(), Rejection<'a>> {
+        if let Some(rule) = self.exclude.iter().find(|rule| rule.is_match(text)) {
+            return Err(Rejection::Excluded(rule));
+        }
+
+        if !self.include.is_empty() && !self.include.iter().any(|rule| rule.is_match(text)) {
+            return Err(Rejection::NotIncluded(self.include));
+        }
+
+        Ok(())
+    }
+}
+
+/// The reason a magnet was dropped
+#[derive(Debug)]
+pub enum Rejection<'a> {
+    Before(DateTime<Utc>),
+    TooSmall(&'a Size),
+    TooLarge(&'a Size),
+    TooFewSeeders(u32),
+    Untrusted(Option<String>),
+    Excluded(&'a Rule),
+    NotIncluded(&'a [Rule]),
+}
+
+impl fmt::Display for Rejection<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Before(time) => write!(f, "uploaded before {}", time.format("%F %R UTC")),
+            Rejection::TooSmall(size) => write!(f, "smaller than {size}"),
+            Rejection::TooLarge(size) => write!(f, "larger than {size}"),
+            Rejection::TooFewSeeders(count) => write!(f, "fewer than {count} seeders"),
+            Rejection::Untrusted(Some(uploader)) => write!(f, "uploader {uploader} not trusted"),
+            Rejection::Untrusted(None) => f.write_str("anonymous upload"),
+            Rejection::Excluded(rule) => write!(f, "matched exclude {rule}"),
+            Rejection::NotIncluded(rules) => {
+                f.write_str("matched no include rule (")?;
+                for (idx, rule) in rules.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{rule}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rejection, Rule, Rules};
+
+    fn rules(rules: &[&str]) -> Vec<Rule> {
+        rules.iter().map(|rule| rule.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn keywords_ignore_case() {
+        let rule: Rule = "Sample".parse().unwrap();
+        assert!(rule.is_match("Some Release SAMPLE"));
+        assert!(!rule.is_match("Some Release"));
+    }
+
+    #[test]
+    fn regexes_are_case_sensitive_unless_asked() {
+        let rule: Rule = r"/\b720p\b/".parse().unwrap();
+        assert!(rule.is_match("Some Release 720p"));
+        assert!(!rule.is_match("Some Release 720P"));
+        assert!(!rule.is_match("Some Release 1720p"));
+
+        let rule: Rule = r"/\b720p\b/i".parse().unwrap();
+        assert!(rule.is_match("Some Release 720P"));
+    }
+
+    #[test]
+    fn bad_regex_is_an_error() {
+        assert!("/(unclosed/".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let include = rules(&["1080p", "/2160p|4k/i"]);
+        let exclude = rules(&["sample"]);
+        let rules = Rules {
+            include: &include,
+            exclude: &exclude,
+        };
+
+        assert!(rules.check("Release 1080p").is_ok());
+        assert!(rules.check("Release 4K").is_ok());
+        assert!(matches!(
+            rules.check("Release 1080p sample"),
+            Err(Rejection::Excluded(_))
+        ));
+
+        let rejection = rules.check("Release 720p").unwrap_err();
+        assert_eq!(
+            rejection.to_string(),
+            r#"matched no include rule (keyword "1080p", regex /2160p|4k/)"#
+        );
+    }
+}
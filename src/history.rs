@@ -1,110 +1,451 @@
 use std::{
-    fs::{self, File},
-    hash::Hash,
-    io,
-    path::PathBuf,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use chrono::{Date, DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
 use directories::UserDirs;
-use hashbrown::HashSet;
-use regex::Regex;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use hashbrown::HashMap;
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Deserializer};
 
-use crate::magnet::Magnet;
+use crate::{
+    magnet::{canonical_infohash, Magnet},
+    size::Size,
+};
+
+static SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS items (
+        infohash    TEXT PRIMARY KEY NOT NULL,
+        title       TEXT NOT NULL,
+        link        TEXT NOT NULL,
+        size        INTEGER NOT NULL,
+        feed        TEXT NOT NULL,
+        date        TEXT NOT NULL,
+        first_seen  TEXT NOT NULL,
+        emitted     INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS items_by_feed ON items (feed, first_seen);
+    CREATE TABLE IF NOT EXISTS deliveries (
+        infohash    TEXT NOT NULL,
+        sink        TEXT NOT NULL,
+        delivered   TEXT NOT NULL,
+        PRIMARY KEY (infohash, sink)
+    );
+    CREATE TABLE IF NOT EXISTS sinks (
+        name        TEXT PRIMARY KEY NOT NULL,
+        added       TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS runs (
+        feed        TEXT PRIMARY KEY NOT NULL,
+        last_run    TEXT NOT NULL
+    );
+";
 
-#[derive(Clone, Debug, Eq, Deserialize, Serialize)]
+/// An entry from the original JSON history file
+#[derive(Clone, Debug, Deserialize)]
 struct Entry {
     magnet: String,
 
-    // Because chrono::Date cannot be serialized directly, we provide
-    // custom implementations converting to and from datetime.
-    #[serde(
-        deserialize_with = "deserialize_date",
-        serialize_with = "serialize_date"
-    )]
-    date: Date<Utc>,
+    // Dates were written as midnight on the day of the upload.
+    #[serde(deserialize_with = "deserialize_legacy_date")]
+    date: DateTime<Utc>,
 }
 
-impl Hash for Entry {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.magnet.hash(state);
-    }
+/// A magnet as recorded in history
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub infohash: String,
+    pub title: String,
+    /// Empty for records imported from the old history file.
+    pub link: String,
+    pub size: u64,
+    pub feed: String,
+    pub date: DateTime<Utc>,
+    pub first_seen: DateTime<Utc>,
+    pub emitted: bool,
 }
 
-impl PartialEq for Entry {
-    fn eq(&self, other: &Self) -> bool {
-        self.magnet == other.magnet
+impl Record {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Record {
+            infohash: row.get("infohash")?,
+            title: row.get("title")?,
+            link: row.get("link")?,
+            size: row.get::<_, i64>("size")? as u64,
+            feed: row.get("feed")?,
+            date: parse_datetime(&row.get::<_, String>("date")?)?,
+            first_seen: parse_datetime(&row.get::<_, String>("first_seen")?)?,
+            emitted: row.get("emitted")?,
+        })
+    }
+
+    /// The magnet as far as history remembers it: enough to send it on, but no peers.
+    pub fn magnet(&self) -> Magnet {
+        Magnet {
+            text: self.title.clone(),
+            link: self.link.clone(),
+            size: Size::from_bytes(self.size),
+            date: self.date,
+            seeders: 0,
+            leechers: 0,
+            uploader: None,
+            kind: None,
+            category: None,
+            feed: self.feed.clone(),
+        }
     }
 }
 
+/// Which records to list
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Query<'a> {
+    pub feed: Option<&'a str>,
+    /// Matched anywhere in the title, ignoring case.
+    pub text: Option<&'a str>,
+    /// Records first seen on or after this date.
+    pub since: Option<NaiveDate>,
+    /// Records first seen on or before this date.
+    pub until: Option<NaiveDate>,
+    /// Include records which never made it into a listing.
+    pub all: bool,
+}
+
+/// How much a feed has turned up
 #[derive(Clone, Debug)]
+pub struct FeedStats {
+    pub feed: String,
+    pub seen: usize,
+    pub emitted: usize,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Everything yogo has seen, stored in SQLite
+///
+/// Every magnet a feed turns up is recorded, along with whether it made it into the listing.
+/// Only emitted magnets count as "seen" for the purpose of filtering; something dropped by a
+/// filter today will still show up tomorrow if the filter changes.
+///
+/// It also keeps the time of each feed's last successful run, so the next run knows how far
+/// back to look.
+///
+/// Changes are held in memory until `write` so that a failed run leaves history untouched.
+#[derive(Debug)]
 pub struct History {
-    entries: HashSet<Entry>,
-    hash_pattern: Regex,
+    connection: Mutex<Connection>,
+    pending: HashMap<String, Record>,
+    runs: HashMap<String, DateTime<Utc>>,
+    pending_runs: HashMap<String, DateTime<Utc>>,
 }
 
 impl History {
-    pub fn load() -> io::Result<Self> {
-        let path = get_history_path()?;
-        if !path.exists() {
-            return Ok(History::default());
+    /// Opens the history database at `path`, first importing the JSON history file at `legacy`
+    /// if there is one. The JSON file is renamed afterward so it's only imported once.
+    pub fn load(path: impl AsRef<Path>, legacy: Option<&Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        if let Some(legacy) = legacy.filter(|legacy| legacy.exists()) {
+            import_legacy(&mut connection, legacy)?;
+            let mut migrated = legacy.as_os_str().to_owned();
+            migrated.push(".migrated");
+            fs::rename(legacy, migrated)?;
         }
 
-        let text = fs::read_to_string(&path)?;
+        let runs = connection
+            .prepare("SELECT feed, last_run FROM runs")?
+            .query_map([], |row| {
+                Ok((row.get(0)?, parse_datetime(&row.get::<_, String>(1)?)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
 
         Ok(History {
-            entries: serde_json::from_str(&text)?,
-            ..Default::default()
+            connection: Mutex::new(connection),
+            pending: HashMap::new(),
+            runs,
+            pending_runs: HashMap::new(),
         })
     }
 
-    pub fn filter(&mut self, magnet: &Magnet) -> bool {
-        self.create_entry(magnet)
-            .map(|entry| self.entries.insert(entry))
-            .unwrap_or_default()
+    /// When a feed last finished without error, as of the last `write`.
+    pub fn last_run(&self, feed: &str) -> Option<DateTime<Utc>> {
+        self.runs.get(feed).copied()
+    }
+
+    /// Records that a feed finished without error. `started` should be when the run began, so
+    /// anything uploaded while it was underway is looked for again next time.
+    pub fn finish_run(&mut self, feed: &str, started: DateTime<Utc>) {
+        self.pending_runs.insert(feed.into(), started);
+    }
+
+    /// Records a magnet turned up by a feed, whether or not it ends up in the listing.
+    pub fn record(&mut self, magnet: &Magnet) {
+        if let Some(record) = self.create_record(magnet) {
+            self.pending
+                .entry(record.infohash.clone())
+                .or_insert(record);
+        }
+    }
+
+    /// Checks for an emitted magnet without recording anything.
+    pub fn contains(&self, magnet: &Magnet) -> anyhow::Result<bool> {
+        match magnet.infohash() {
+            Some(hash) => Ok(self.is_emitted(&hash)?),
+            None => Ok(false),
+        }
     }
 
-    pub fn write(&self, limit: Date<Utc>) -> io::Result<()> {
-        // So, regarding the bug report from 26 May 2022, here's the plan: we're going to keep
-        // history items a *full day* beyond the filter date. This is on the assumption that there
-        // is some kind of day/hour tomfoolishness going on. Adding some slack here will cost
-        // almost nothing and potentially solve this issue without any further research required.
+    /// Marks a magnet as emitted, returning false if it was emitted before.
+    pub fn filter(&mut self, magnet: &Magnet) -> anyhow::Result<bool> {
+        let Some(record) = self.create_record(magnet) else {
+            return Ok(false);
+        };
+
+        if self.is_emitted(&record.infohash)? {
+            return Ok(false);
+        }
+
+        // The feed which listed it gets the credit, whichever feed happened to turn it up first.
+        let feed = record.feed.clone();
+        let pending = self
+            .pending
+            .entry(record.infohash.clone())
+            .or_insert(record);
+        pending.emitted = true;
+        pending.feed = feed;
+        Ok(true)
+    }
 
+    /// Writes pending records and run times, and clears out stale records.
+    ///
+    /// Emitted records are kept indefinitely. Records for magnets that never made the listing
+    /// are only useful while they're within the filter window, so those uploaded before `limit`
+    /// (with a day's slack) are removed.
+    pub fn write(&mut self, limit: DateTime<Utc>) -> anyhow::Result<()> {
         let slack_date = safe_decrement_limit(limit);
-        let entries: HashSet<_> = self
-            .entries
-            .iter()
-            .cloned()
-            .filter(|entry| entry.date >= slack_date)
-            .collect();
-
-        let new_history = get_new_history_path()?;
-        let history = get_history_path()?;
-        let mut file = File::create(&new_history)?;
-        serde_json::to_writer_pretty(&mut file, &entries)?;
-        fs::rename(&new_history, &history)
-    }
-
-    fn create_entry(&self, magnet: &Magnet) -> Option<Entry> {
-        let hash = self.hash_pattern.captures(&magnet.link)?.get(1)?.as_str();
-        Some(Entry {
-            magnet: hash.into(),
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction()?;
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO items (infohash, title, link, size, feed, date, first_seen, emitted)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (infohash) DO UPDATE SET emitted = MAX(emitted, excluded.emitted)",
+            )?;
+
+            for record in self.pending.values() {
+                insert.execute(params![
+                    record.infohash,
+                    record.title,
+                    record.link,
+                    record.size as i64,
+                    record.feed,
+                    record.date.to_rfc3339(),
+                    record.first_seen.to_rfc3339(),
+                    record.emitted,
+                ])?;
+            }
+        }
+
+        for (feed, last_run) in &self.pending_runs {
+            tx.execute(
+                "INSERT OR REPLACE INTO runs (feed, last_run) VALUES (?1, ?2)",
+                params![feed, last_run.to_rfc3339()],
+            )?;
+        }
+
+        tx.execute(
+            "DELETE FROM items WHERE emitted = 0 AND date < ?1",
+            params![slack_date.to_rfc3339()],
+        )?;
+        tx.commit()?;
+
+        self.pending.clear();
+        self.runs.extend(self.pending_runs.drain());
+        Ok(())
+    }
+
+    /// Lists records matching a query, newest first.
+    pub fn query(&self, query: &Query) -> anyhow::Result<Vec<Record>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT * FROM items
+             WHERE (?1 IS NULL OR feed = ?1)
+               AND (?2 IS NULL OR instr(lower(title), lower(?2)) > 0)
+               AND (?3 IS NULL OR first_seen >= ?3)
+               AND (?4 IS NULL OR first_seen < ?4)
+               AND (?5 OR emitted)
+             ORDER BY first_seen DESC, title",
+        )?;
+
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+        let since = query.since.map(|date| midnight(date).to_rfc3339());
+        let until = query
+            .until
+            .map(|date| (midnight(date) + Duration::days(1)).to_rfc3339());
+        let records = statement
+            .query_map(
+                params![query.feed, query.text, since, until, query.all],
+                Record::from_row,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    /// Starts keeping track of a sink, if it's new. A sink is owed every magnet listed from then
+    /// on, but nothing from before, so adding one doesn't send it the whole history.
+    pub fn add_sink(&self, sink: &str, added: DateTime<Utc>) -> anyhow::Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR IGNORE INTO sinks (name, added) VALUES (?1, ?2)",
+            params![sink, added.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Listed magnets a sink is owed but doesn't have yet, oldest first. This includes any
+    /// which failed to send before, as well as everything new since the last `write`.
+    pub fn undelivered(&self, sink: &str) -> anyhow::Result<Vec<Record>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT items.* FROM items JOIN sinks ON sinks.name = ?1
+             WHERE emitted AND link <> '' AND first_seen >= sinks.added
+               AND NOT EXISTS (
+                   SELECT 1 FROM deliveries
+                   WHERE deliveries.infohash = items.infohash AND deliveries.sink = ?1
+               )
+             ORDER BY first_seen, title",
+        )?;
+
+        let records = statement
+            .query_map(params![sink], Record::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    /// Records a delivery. Unlike everything else, this is written immediately: the client
+    /// has the magnet now, whether or not the rest of the run succeeds.
+    pub fn mark_delivered(&self, infohash: &str, sink: &str) -> anyhow::Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR IGNORE INTO deliveries (infohash, sink, delivered) VALUES (?1, ?2, ?3)",
+            params![infohash, sink, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Removes a record, so the magnet will be listed again. Returns false if there was none.
+    pub fn forget(&mut self, infohash: &str) -> anyhow::Result<bool> {
+        let infohash = canonical_infohash(infohash).unwrap_or_else(|| infohash.to_lowercase());
+        self.pending.remove(&infohash);
+        let connection = self.connection.lock().unwrap();
+        let count =
+            connection.execute("DELETE FROM items WHERE infohash = ?1", params![infohash])?;
+        connection.execute(
+            "DELETE FROM deliveries WHERE infohash = ?1",
+            params![infohash],
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Removes records for magnets uploaded before a date, returning how many were removed.
+    pub fn prune(&self, before: DateTime<Utc>) -> anyhow::Result<usize> {
+        let connection = self.connection.lock().unwrap();
+        let count = connection.execute(
+            "DELETE FROM items WHERE date < ?1",
+            params![before.to_rfc3339()],
+        )?;
+        connection.execute(
+            "DELETE FROM deliveries WHERE infohash NOT IN (SELECT infohash FROM items)",
+            [],
+        )?;
+        Ok(count)
+    }
+
+    pub fn stats(&self) -> anyhow::Result<Vec<FeedStats>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT feed, COUNT(*), SUM(emitted), MAX(first_seen) FROM items
+             GROUP BY feed ORDER BY feed",
+        )?;
+
+        let stats = statement
+            .query_map([], |row| {
+                Ok(FeedStats {
+                    feed: row.get(0)?,
+                    seen: row.get::<_, i64>(1)? as usize,
+                    emitted: row.get::<_, i64>(2)? as usize,
+                    last_seen: parse_datetime(&row.get::<_, String>(3)?)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(stats)
+    }
+
+    fn is_emitted(&self, hash: &str) -> rusqlite::Result<bool> {
+        if let Some(record) = self.pending.get(hash) {
+            if record.emitted {
+                return Ok(true);
+            }
+        }
+
+        let connection = self.connection.lock().unwrap();
+        let emitted = connection
+            .query_row(
+                "SELECT emitted FROM items WHERE infohash = ?1",
+                params![hash],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()?;
+        Ok(emitted.unwrap_or_default())
+    }
+
+    fn create_record(&self, magnet: &Magnet) -> Option<Record> {
+        Some(Record {
+            infohash: magnet.infohash()?,
+            title: magnet.text.clone(),
+            link: magnet.link.clone(),
+            size: magnet.size.bytes(),
+            feed: magnet.feed.clone(),
             date: magnet.date,
+            first_seen: Utc::now(),
+            emitted: false,
         })
     }
 }
 
-impl Default for History {
-    fn default() -> Self {
-        Self {
-            entries: Default::default(),
-            hash_pattern: Regex::new(r#"btih:([^&]+)"#).unwrap(),
+/// Imports the JSON history file all at once, or not at all.
+fn import_legacy(connection: &mut Connection, path: &Path) -> anyhow::Result<()> {
+    let text = fs::read_to_string(path)?;
+    let legacy: Vec<Entry> = serde_json::from_str(&text)?;
+
+    let tx = connection.transaction()?;
+    {
+        // The old format kept nothing but the hash and the upload date, so that's all we have.
+        let mut insert = tx.prepare(
+            "INSERT OR IGNORE INTO items (infohash, title, link, size, feed, date, first_seen, emitted)
+             VALUES (?1, '', '', 0, '', ?2, ?3, 1)",
+        )?;
+        for entry in legacy {
+            let infohash =
+                canonical_infohash(&entry.magnet).unwrap_or_else(|| entry.magnet.to_lowercase());
+            let date = entry.date.to_rfc3339();
+            insert.execute(params![infohash, date, date])?;
         }
     }
+    tx.commit()?;
+
+    Ok(())
 }
 
-fn get_history_path() -> io::Result<PathBuf> {
+/// The JSON history file kept by older versions, which belongs to the default profile
+pub fn legacy_path() -> io::Result<PathBuf> {
     let directories = UserDirs::new()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "user dirs not found"))?;
 
@@ -113,36 +454,262 @@ fn get_history_path() -> io::Result<PathBuf> {
     Ok(history)
 }
 
-fn get_new_history_path() -> io::Result<PathBuf> {
-    let directories = UserDirs::new()
-        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "user dirs not found"))?;
+/// Reads a legacy date, which is normally an RFC 3339 datetime. A bare date (with or without the
+/// "UTC" suffix chrono's old `Date` printed) is taken as midnight UTC.
+fn deserialize_legacy_date<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::deserialize(d)?;
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(&text) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
 
-    let mut history = directories.home_dir().to_owned();
-    history.push(".yogo_history.new");
-    Ok(history)
+    NaiveDate::parse_from_str(text.trim_end_matches("UTC"), "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(serde::de::Error::custom)
+}
+
+fn parse_datetime(s: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(conversion_error)
 }
 
-fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Date<Utc>, D::Error> {
-    let datetime: DateTime<Utc> = Deserialize::deserialize(deserializer)?;
-    Ok(datetime.date())
+fn conversion_error(e: chrono::ParseError) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))
 }
 
-fn serialize_date<S: Serializer>(date: &Date<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
-    let datetime = date.and_hms(0, 0, 0);
-    datetime.serialize(serializer)
+/// A day before `limit`, for comparisons where a little slack costs nothing.
+pub fn safe_decrement_limit(limit: DateTime<Utc>) -> DateTime<Utc> {
+    limit - Duration::days(1)
 }
 
-fn safe_decrement_limit(limit: Date<Utc>) -> Date<Utc> {
-    // The plan here is to decrement limit by 24 hours and, if that proves insufficient to
-    // decrease the day by one, to decrement limit by 25 hours instead. WHETHER OR NOT ANY OF THIS
-    // MATTERS is a matter of complete speculation. There is API space in dotnet to cover this
-    // case in an affirmative way (think AddDays(n)/SubtractDays(m)), but the situation here is,
-    // at least for me, ambiguous with regard to time changes and leap seconds and all that shit.
-
-    let result = limit - Duration::hours(24);
-    if result == limit {
-        limit - Duration::hours(25)
-    } else {
-        result
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{History, Query};
+    use crate::{magnet::Magnet, testing::TempDir};
+
+    fn magnet(hash: &str) -> Magnet {
+        Magnet {
+            text: format!("Release {hash}"),
+            link: format!("magnet:?xt=urn:btih:{}&dn=Release", hash.repeat(10)),
+            feed: "deen".into(),
+            ..Magnet::sample()
+        }
+    }
+
+    #[test]
+    fn only_emitted_magnets_are_seen() {
+        let dir = TempDir::new("emitted");
+        let path = dir.join("history.sqlite");
+        let legacy = dir.join("history.json");
+
+        let mut history = History::load(&path, Some(&legacy)).unwrap();
+        history.record(&magnet("AAAA"));
+        history.record(&magnet("BBBB"));
+        assert!(history.filter(&magnet("AAAA")).unwrap());
+        assert!(!history.filter(&magnet("AAAA")).unwrap());
+        history
+            .write(Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap())
+            .unwrap();
+
+        let history = History::load(&path, Some(&legacy)).unwrap();
+        assert!(history.contains(&magnet("AAAA")).unwrap());
+        assert!(!history.contains(&magnet("BBBB")).unwrap());
+
+        // The same hash in base32 is the same torrent.
+        let mut base32 = magnet("AAAA");
+        base32.link = "magnet:?xt=urn:btih:VKVKVKVKVKVKVKVKVKVKVKVKVKVKVKVK".into();
+        assert!(history.contains(&base32).unwrap());
+
+        let query = Query {
+            feed: Some("deen"),
+            all: true,
+            ..Default::default()
+        };
+        assert_eq!(history.query(&query).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn stale_unemitted_magnets_are_pruned() {
+        let dir = TempDir::new("pruned");
+        let path = dir.join("history.sqlite");
+        let legacy = dir.join("history.json");
+
+        let mut history = History::load(&path, Some(&legacy)).unwrap();
+        history.record(&magnet("AAAA"));
+        history.filter(&magnet("BBBB")).unwrap();
+        history
+            .write(Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap())
+            .unwrap();
+
+        let query = Query {
+            all: true,
+            ..Default::default()
+        };
+        let records = history.query(&query).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].infohash, "bbbb".repeat(10));
+    }
+
+    #[test]
+    fn run_times_are_kept_once_written() {
+        let dir = TempDir::new("runs");
+        let path = dir.join("history.sqlite");
+        let started = Utc.with_ymd_and_hms(2022, 6, 1, 12, 0, 0).unwrap();
+
+        let mut history = History::load(&path, None).unwrap();
+        assert_eq!(history.last_run("deen"), None);
+        history.finish_run("deen", started);
+        assert_eq!(history.last_run("deen"), None);
+        history
+            .write(Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap())
+            .unwrap();
+        assert_eq!(history.last_run("deen"), Some(started));
+
+        let history = History::load(&path, None).unwrap();
+        assert_eq!(history.last_run("deen"), Some(started));
+        assert_eq!(history.last_run("other"), None);
+    }
+
+    #[test]
+    fn history_can_be_searched_and_edited() {
+        let dir = TempDir::new("edited");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        let mut other = magnet("CCCC");
+        other.text = "Other Thing".into();
+        other.feed = "other".into();
+        other.date = Utc.with_ymd_and_hms(2022, 4, 1, 0, 0, 0).unwrap();
+
+        history.filter(&magnet("AAAA")).unwrap();
+        history.filter(&other).unwrap();
+        history.record(&magnet("BBBB"));
+        history
+            .write(Utc.with_ymd_and_hms(2022, 4, 1, 0, 0, 0).unwrap())
+            .unwrap();
+
+        let search = Query {
+            text: Some("release"),
+            ..Default::default()
+        };
+        let records = history.query(&search).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].title, "Release AAAA");
+
+        let stats = history.stats().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            (stats[0].feed.as_str(), stats[0].seen, stats[0].emitted),
+            ("deen", 2, 1)
+        );
+
+        assert!(history.forget(&"AAAA".repeat(10)).unwrap());
+        assert!(!history.forget(&"aaaa".repeat(10)).unwrap());
+        assert!(!history.contains(&magnet("AAAA")).unwrap());
+
+        assert_eq!(
+            history
+                .prune(Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap())
+                .unwrap(),
+            1
+        );
+        assert!(!history.contains(&other).unwrap());
+    }
+
+    #[test]
+    fn queries_can_be_bounded_by_date() {
+        let dir = TempDir::new("bounded");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        history.filter(&magnet("AAAA")).unwrap();
+        history
+            .write(Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap())
+            .unwrap();
+
+        let today = Utc::now().date_naive();
+        let until = |until| Query {
+            until: Some(until),
+            ..Default::default()
+        };
+        assert_eq!(history.query(&until(today)).unwrap().len(), 1);
+        let yesterday = today.pred_opt().unwrap();
+        assert!(history.query(&until(yesterday)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn database_errors_are_not_read_as_unseen() {
+        let dir = TempDir::new("broken");
+        let path = dir.join("history.sqlite");
+        let mut history = History::load(&path, None).unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection.execute_batch("DROP TABLE items").unwrap();
+
+        assert!(history.contains(&magnet("AAAA")).is_err());
+        assert!(history.filter(&magnet("AAAA")).is_err());
+    }
+
+    #[test]
+    fn deliveries_are_kept_per_sink() {
+        let dir = TempDir::new("delivered");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        let hash = "aaaa".repeat(10);
+        let limit = Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap();
+        let undelivered = |history: &History, sink| history.undelivered(sink).unwrap().len();
+
+        let added = Utc::now();
+        history.add_sink("qbittorrent", added).unwrap();
+        history.add_sink("transmission", added).unwrap();
+        history.filter(&magnet("AAAA")).unwrap();
+        history.record(&magnet("BBBB"));
+        history.write(limit).unwrap();
+        assert_eq!(undelivered(&history, "qbittorrent"), 1);
+        assert_eq!(
+            history.undelivered("qbittorrent").unwrap()[0].magnet().link,
+            magnet("AAAA").link
+        );
+
+        history.mark_delivered(&hash, "qbittorrent").unwrap();
+        history.mark_delivered(&hash, "qbittorrent").unwrap();
+        assert_eq!(undelivered(&history, "qbittorrent"), 0);
+        assert_eq!(undelivered(&history, "transmission"), 1);
+
+        // A sink added later is owed only what's listed from then on.
+        history.add_sink("later", Utc::now()).unwrap();
+        assert_eq!(undelivered(&history, "later"), 0);
+
+        // Forgetting a magnet forgets its deliveries too, so it's sent again if listed again.
+        history.forget(&hash).unwrap();
+        history.filter(&magnet("AAAA")).unwrap();
+        history.write(limit).unwrap();
+        assert_eq!(undelivered(&history, "qbittorrent"), 1);
+    }
+
+    #[test]
+    fn json_history_is_migrated() {
+        let dir = TempDir::new("migrated");
+        let path = dir.join("history.sqlite");
+        let legacy = dir.join("history.json");
+        fs::write(
+            &legacy,
+            r#"[
+                {"magnet":"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA","date":"2022-05-02T00:00:00Z"},
+                {"magnet":"BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB","date":"2022-05-03UTC"},
+                {"magnet":"CCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCC","date":"2022-05-04"}
+            ]"#,
+        )
+        .unwrap();
+
+        let history = History::load(&path, Some(&legacy)).unwrap();
+        assert!(history.contains(&magnet("AAAA")).unwrap());
+        let records = history.query(&Query::default()).unwrap();
+        let dates: Vec<_> = records.iter().map(|record| record.date).collect();
+        assert_eq!(
+            dates,
+            [4, 3, 2].map(|day| Utc.with_ymd_and_hms(2022, 5, day, 0, 0, 0).unwrap())
+        );
+        assert!(!legacy.exists());
+        assert!(dir.join("history.json.migrated").exists());
     }
 }
@@ -1,78 +1,19 @@
 use std::{error, fmt, num::ParseIntError, str::FromStr};
 
-use chrono::{Date, Datelike, Duration, TimeZone, Utc};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use data_encoding::{BASE32, HEXLOWER, HEXLOWER_PERMISSIVE};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
-struct MagnetDate(Date<Utc>);
-
-impl MagnetDate {
-    fn into_inner(self) -> Date<Utc> {
-        self.0
-    }
-}
-
-impl FromStr for MagnetDate {
-    type Err = ParseMagnetDateErr;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Uploaded 05-02&nbsp;18:37, Size 580.9&nbsp;MiB, ULed by
-        // Uploaded 12-13&nbsp;2021, Size 487.16&nbsp;MiB, ULed by
-
-        let text = s
-            .split_ascii_whitespace()
-            .nth(1)
-            .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?;
-        let mut tokens = text.split('-').flat_map(|token| token.split("&nbsp;"));
-
-        let month_candidate = tokens
-            .next()
-            .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?;
-
-        // This was uploaded "today" or near enough to today as makes no difference....
-        if month_candidate == "Today" || month_candidate.starts_with("<b>") {
-            return Ok(MagnetDate(Utc::today()));
-        }
-
-        // This was uploaded "yesterday"
-        // Go with yesterday's date (in UTC) and bail
-        if month_candidate == "Y" {
-            let today = Utc::today();
-            let yesterday = today - Duration::hours(24);
-            return Ok(MagnetDate(yesterday));
-        }
-
-        let month = month_candidate
-            .parse()
-            .map_err(|e| ParseMagnetDateErr::BadInteger(s.into(), e))?;
-        let day: u32 = tokens
-            .next()
-            .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?
-            .parse()
-            .map_err(|e| ParseMagnetDateErr::BadInteger(s.into(), e))?;
-
-        let year_candidate = tokens
-            .next()
-            .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?;
-
-        // The link was posted this year, so the third segment records the UTC time of the posting.
-        let year = if year_candidate.contains(':') {
-            Utc::now().year()
-        } else {
-            year_candidate
-                .trim_end_matches(',')
-                .parse()
-                .map_err(|e| ParseMagnetDateErr::BadInteger(s.into(), e))?
-        };
-
-        Ok(MagnetDate(Utc.ymd(year, month, day)))
-    }
-}
+use crate::{config::Feed, size::Size, source::Source};
 
 #[derive(Debug)]
 pub enum ParseMagnetDateErr {
     BadSplit(String),
     BadInteger(String, ParseIntError),
+    /// Numbers that don't make a date or time, or a local time the clocks skipped.
+    NoSuchTime(String),
 }
 
 impl fmt::Display for ParseMagnetDateErr {
@@ -80,6 +21,7 @@ impl fmt::Display for ParseMagnetDateErr {
         match self {
             ParseMagnetDateErr::BadSplit(info) => write!(f, "Unable to split correctly:\n{info}"),
             ParseMagnetDateErr::BadInteger(s, e) => write!(f, "Unable to parse: {e}\n{s}"),
+            ParseMagnetDateErr::NoSuchTime(s) => write!(f, "No such time:\n{s}"),
         }
     }
 }
@@ -89,49 +31,107 @@ impl error::Error for ParseMagnetDateErr {}
 /// A magnet link
 ///
 /// This object is constructed based on a MagnetContext.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Magnet {
     pub text: String,
     pub link: String,
-    pub size: String,
-    #[serde(deserialize_with = "datetime_as_date")]
-    pub date: Date<Utc>,
-}
+    pub size: Size,
+    /// When the magnet was uploaded. Where the index gives only a day, this is midnight.
+    pub date: DateTime<Utc>,
+
+    #[serde(default)]
+    pub seeders: u32,
+    #[serde(default)]
+    pub leechers: u32,
+
+    /// None for anonymous uploads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploader: Option<String>,
 
-fn datetime_as_date<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Date<Utc>, D::Error> {
-    let datetime: chrono::DateTime<Utc> = serde::Deserialize::deserialize(d)?;
-    Ok(datetime.date())
+    /// The index's own category for the upload, e.g. "Porn > HD - Movies".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+
+    /// Category of the feed which turned up this magnet, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// Name of the feed which turned up this magnet.
+    #[serde(default)]
+    pub feed: String,
 }
 
-impl TryFrom<MagnetContext<'_>> for Magnet {
-    type Error = ParseMagnetDateErr;
+impl Magnet {
+    /// The canonical infohash of the magnet link, if it can be parsed.
+    pub fn infohash(&self) -> Option<String> {
+        self.link
+            .parse::<MagnetUri>()
+            .ok()
+            .map(|uri| uri.infohash())
+    }
 
-    fn try_from(
+    pub fn from_context(
         MagnetContext {
             text,
             link,
             size,
             info,
+            seeders,
+            leechers,
+            uploader,
+            kind,
         }: MagnetContext,
-    ) -> Result<Self, Self::Error> {
-        let date: MagnetDate = info.parse()?;
+        source: &dyn Source,
+        feed: &Feed,
+        tz: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<Self, ParseMagnetDateErr> {
         Ok(Self {
             text,
             link: link.into(),
             size,
-            date: date.into_inner(),
+            date: source.parse_date(&info, tz, now)?,
+            seeders,
+            leechers,
+            uploader,
+            kind,
+            category: feed.category.clone(),
+            feed: feed.name().into(),
         })
     }
 }
 
+#[cfg(test)]
+impl Magnet {
+    /// An upload for tests to build on, e.g. `Magnet { seeders: 5, ..Magnet::sample() }`.
+    pub fn sample() -> Self {
+        Magnet {
+            text: "Sample Release".into(),
+            link: "magnet:?xt=urn:btih:F657AEFD637DBCC4E3AC965DDB7CCA0C11BC157C".into(),
+            size: "580.9 MiB".parse().unwrap(),
+            date: "2022-05-02T00:00:00Z".parse().unwrap(),
+            seeders: 0,
+            leechers: 0,
+            uploader: None,
+            kind: None,
+            category: None,
+            feed: "test".into(),
+        }
+    }
+}
+
 /// Constructor context for Magnet
 ///
 /// This object is pulled directly from the HTML via the scraper.
 pub struct MagnetContext<'a> {
     pub text: String,
     pub link: &'a str,
-    pub size: String,
+    pub size: Size,
     pub info: String,
+    pub seeders: u32,
+    pub leechers: u32,
+    pub uploader: Option<String>,
+    pub kind: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -140,6 +140,7 @@ pub enum ExtractMagnetContextErr {
     MagnetLink(String),
     Size(String),
     Info(String),
+    Peers(String),
 }
 
 impl fmt::Display for ExtractMagnetContextErr {
@@ -149,25 +150,342 @@ impl fmt::Display for ExtractMagnetContextErr {
             ExtractMagnetContextErr::MagnetLink(html) => write!(f, "Bad magnet link:\n{html}"),
             ExtractMagnetContextErr::Size(s) => write!(f, "Unable to determine size: {s}"),
             ExtractMagnetContextErr::Info(html) => write!(f, "Bad info:\n{html}"),
+            ExtractMagnetContextErr::Peers(html) => write!(f, "Bad seeders/leechers:\n{html}"),
         }
     }
 }
 
 impl error::Error for ExtractMagnetContextErr {}
 
+/// Characters left alone when writing a magnet uri: the unreserved set from RFC 3986
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// A parsed magnet uri
+///
+/// Infohashes are canonicalized on parsing, so hex and base32 forms of the same v1 hash, or
+/// upper- and lowercase hex, compare equal. Parameters we don't know about are kept as written.
+///
+/// Only parsing makes one, so there's always at least one infohash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MagnetUri {
+    /// v1 (SHA-1) infohash, from `xt=urn:btih:`
+    btih: Option<[u8; 20]>,
+    /// v2 infohash as a multihash, from `xt=urn:btmh:`
+    btmh: Option<Vec<u8>>,
+    /// `dn`
+    pub name: Option<String>,
+    /// `xl`
+    pub length: Option<u64>,
+    /// `tr`
+    pub trackers: Vec<String>,
+    /// `ws`
+    pub web_seeds: Vec<String>,
+    extra: Vec<(String, String)>,
+}
+
+impl MagnetUri {
+    /// The key used to tell torrents apart: the v1 hash in lowercase hex, or for v2-only
+    /// torrents, the v2 multihash in lowercase hex.
+    pub fn infohash(&self) -> String {
+        match (&self.btih, &self.btmh) {
+            (Some(btih), _) => HEXLOWER.encode(btih),
+            (None, Some(btmh)) => HEXLOWER.encode(btmh),
+            (None, None) => unreachable!("a magnet uri always has an infohash"),
+        }
+    }
+}
+
+/// Canonicalizes a v1 infohash written in hex or base32, or a v2 multihash written in hex.
+pub fn canonical_infohash(hash: &str) -> Option<String> {
+    parse_btih(hash)
+        .map(|btih| HEXLOWER.encode(&btih))
+        .or_else(|| parse_btmh(hash).map(|btmh| HEXLOWER.encode(&btmh)))
+}
+
+fn parse_btih(hash: &str) -> Option<[u8; 20]> {
+    let bytes = match hash.len() {
+        40 => HEXLOWER_PERMISSIVE.decode(hash.as_bytes()).ok()?,
+        32 => BASE32.decode(hash.to_ascii_uppercase().as_bytes()).ok()?,
+        _ => return None,
+    };
+    bytes.try_into().ok()
+}
+
+fn parse_btmh(hash: &str) -> Option<Vec<u8>> {
+    // Only SHA2-256 multihashes (code 0x12, 32 bytes) are used by BitTorrent v2.
+    let bytes = HEXLOWER_PERMISSIVE.decode(hash.as_bytes()).ok()?;
+    match bytes.as_slice() {
+        [0x12, 0x20, digest @ ..] if digest.len() == 32 => Some(bytes),
+        _ => None,
+    }
+}
+
+impl FromStr for MagnetUri {
+    type Err = ParseMagnetUriErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s
+            .strip_prefix("magnet:?")
+            .ok_or_else(|| ParseMagnetUriErr::Scheme(s.into()))?;
+
+        let mut uri = MagnetUri {
+            btih: None,
+            btmh: None,
+            name: None,
+            length: None,
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            extra: Vec::new(),
+        };
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+            // Repeated parameters may be numbered, as in xt.1, xt.2.
+            let name = key.split_once('.').map_or(key, |(name, _)| name);
+            match name {
+                "xt" => {
+                    let xt = decode(value);
+                    if let Some(hash) = xt.strip_prefix("urn:btih:") {
+                        let btih = parse_btih(hash)
+                            .ok_or_else(|| ParseMagnetUriErr::BadInfoHash(xt.clone()))?;
+                        uri.btih = Some(btih);
+                    } else if let Some(hash) = xt.strip_prefix("urn:btmh:") {
+                        let btmh = parse_btmh(hash)
+                            .ok_or_else(|| ParseMagnetUriErr::BadInfoHash(xt.clone()))?;
+                        uri.btmh = Some(btmh);
+                    } else {
+                        uri.extra.push((key.into(), value.into()));
+                    }
+                }
+                // Spaces in names are usually written as +, form style.
+                "dn" => uri.name = Some(decode(&value.replace('+', " "))),
+                "xl" => {
+                    let length = value
+                        .parse()
+                        .map_err(|_| ParseMagnetUriErr::BadLength(value.into()))?;
+                    uri.length = Some(length);
+                }
+                "tr" => uri.trackers.push(decode(value)),
+                "ws" => uri.web_seeds.push(decode(value)),
+                _ => uri.extra.push((key.into(), value.into())),
+            }
+        }
+
+        if uri.btih.is_none() && uri.btmh.is_none() {
+            return Err(ParseMagnetUriErr::NoInfoHash(s.into()));
+        }
+
+        Ok(uri)
+    }
+}
+
+fn decode(value: &str) -> String {
+    percent_decode_str(value).decode_utf8_lossy().into_owned()
+}
+
+impl fmt::Display for MagnetUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("magnet:?")?;
+
+        let mut separator = "";
+        let mut param = |f: &mut fmt::Formatter<'_>, key: &str, value: &dyn fmt::Display| {
+            let result = write!(f, "{separator}{key}={value}");
+            separator = "&";
+            result
+        };
+
+        if let Some(btih) = &self.btih {
+            param(f, "xt", &format_args!("urn:btih:{}", HEXLOWER.encode(btih)))?;
+        }
+        if let Some(btmh) = &self.btmh {
+            param(f, "xt", &format_args!("urn:btmh:{}", HEXLOWER.encode(btmh)))?;
+        }
+        if let Some(name) = &self.name {
+            param(f, "dn", &utf8_percent_encode(name, COMPONENT))?;
+        }
+        if let Some(length) = self.length {
+            param(f, "xl", &length)?;
+        }
+        for tracker in &self.trackers {
+            param(f, "tr", &utf8_percent_encode(tracker, COMPONENT))?;
+        }
+        for web_seed in &self.web_seeds {
+            param(f, "ws", &utf8_percent_encode(web_seed, COMPONENT))?;
+        }
+        for (key, value) in &self.extra {
+            param(f, key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ParseMagnetUriErr {
+    Scheme(String),
+    NoInfoHash(String),
+    BadInfoHash(String),
+    BadLength(String),
+}
+
+impl fmt::Display for ParseMagnetUriErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMagnetUriErr::Scheme(s) => write!(f, "Not a magnet uri: {s}"),
+            ParseMagnetUriErr::NoInfoHash(s) => write!(f, "Magnet uri has no infohash: {s}"),
+            ParseMagnetUriErr::BadInfoHash(xt) => write!(f, "Bad infohash: {xt}"),
+            ParseMagnetUriErr::BadLength(xl) => write!(f, "Bad length: {xl}"),
+        }
+    }
+}
+
+impl error::Error for ParseMagnetUriErr {}
+
 #[cfg(test)]
 mod tests {
-    use crate::magnet::MagnetDate;
+    use super::{canonical_infohash, MagnetUri, ParseMagnetUriErr};
+
+    static HEX: &str = "f657aefd637dbcc4e3ac965ddb7cca0c11bc157c";
+    static BASE32: &str = "6ZL257LDPW6MJY5MSZO5W7GKBQI3YFL4";
+    static BTMH: &str = "1220caf1e1c30e81cb361b9ee167c4aa64228a7fa4fa9f6105232b28ad099f3a302e";
+
+    fn parse(s: &str) -> MagnetUri {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn hex_and_base32_hashes_agree() {
+        let upper = parse(&format!("magnet:?xt=urn:btih:{}", HEX.to_uppercase()));
+        let lower = parse(&format!("magnet:?xt=urn:btih:{HEX}"));
+        let base32 = parse(&format!("magnet:?xt=urn:btih:{BASE32}"));
+        let base32_lower = parse(&format!("magnet:?xt=urn:btih:{}", BASE32.to_lowercase()));
+
+        assert_eq!(upper.infohash(), HEX);
+        assert_eq!(upper, lower);
+        assert_eq!(upper, base32);
+        assert_eq!(upper, base32_lower);
+        assert_eq!(canonical_infohash(BASE32).as_deref(), Some(HEX));
+    }
+
+    #[test]
+    fn parses_every_known_parameter() {
+        let uri = parse(concat!(
+            "magnet:?xt=urn:btih:F657AEFD637DBCC4E3AC965DDB7CCA0C11BC157C",
+            "&dn=Sample+Release+2022+1080p",
+            "&xl=609117798",
+            "&tr=udp%3A%2F%2Ftracker.coppersurfer.tk%3A6969%2Fannounce",
+            "&tr.1=udp%3A%2F%2Ftracker.openbittorrent.com%3A6969%2Fannounce",
+            "&ws=https%3A%2F%2Fexample.com%2Ffile.mp4",
+            "&x.pe=10.0.0.1%3A6881",
+        ));
+
+        assert_eq!(uri.name.as_deref(), Some("Sample Release 2022 1080p"));
+        assert_eq!(uri.length, Some(609117798));
+        assert_eq!(
+            uri.trackers,
+            [
+                "udp://tracker.coppersurfer.tk:6969/announce",
+                "udp://tracker.openbittorrent.com:6969/announce",
+            ]
+        );
+        assert_eq!(uri.web_seeds, ["https://example.com/file.mp4"]);
+        assert!(uri.to_string().ends_with("&x.pe=10.0.0.1%3A6881"));
+    }
 
     #[test]
-    fn can_parse_magnet_date() {
+    fn plus_is_a_space_only_in_names() {
+        let uri = parse(concat!(
+            "magnet:?xt=urn:btih:F657AEFD637DBCC4E3AC965DDB7CCA0C11BC157C",
+            "&dn=Fish+Chips",
+            "&tr=https://tracker.example/announce?key=a+b",
+            "&ws=https://example.com/c++.mp4",
+        ));
+
+        assert_eq!(uri.name.as_deref(), Some("Fish Chips"));
+        assert_eq!(uri.trackers, ["https://tracker.example/announce?key=a+b"]);
+        assert_eq!(uri.web_seeds, ["https://example.com/c++.mp4"]);
+    }
+
+    #[test]
+    fn v2_and_hybrid_magnets() {
+        let v2 = parse(&format!("magnet:?xt=urn:btmh:{BTMH}"));
+        assert_eq!(v2.infohash(), BTMH);
+        assert!(v2.btih.is_none());
+
+        // Hybrid torrents are keyed on the v1 hash, so they match v1-only links.
+        let hybrid = parse(&format!(
+            "magnet:?xt=urn:btih:{HEX}&xt=urn:btmh:{}",
+            BTMH.to_uppercase()
+        ));
+        assert_eq!(hybrid.infohash(), HEX);
+        assert_eq!(hybrid.btmh.as_deref(), v2.btmh.as_deref());
+    }
+
+    #[test]
+    fn round_trips() {
         static CASES: &[&str] = &[
-            "Uploaded 05-02&nbsp;18:37, Size 580.9&nbsp;MiB, ULed by",
-            "Uploaded 12-13&nbsp;2021, Size 487.16&nbsp;MiB, ULed by",
+            "magnet:?xt=urn:btih:f657aefd637dbcc4e3ac965ddb7cca0c11bc157c",
+            "magnet:?xt=urn:btih:f657aefd637dbcc4e3ac965ddb7cca0c11bc157c&dn=Fish%20%26%20Chips",
+            concat!(
+                "magnet:?xt=urn:btih:f657aefd637dbcc4e3ac965ddb7cca0c11bc157c",
+                "&xt=urn:btmh:1220caf1e1c30e81cb361b9ee167c4aa64228a7fa4fa9f6105232b28ad099f3a302e",
+                "&dn=caf%C3%A9%2B1080p&xl=12",
+                "&tr=udp%3A%2F%2Ftracker.example%3A1337%2Fannounce",
+                "&tr=https%3A%2F%2Ftracker.example%2Fannounce%3Fkey%3D1%262",
+                "&ws=https%3A%2F%2Fseed.example%2Ffile",
+                "&so=0-2"
+            ),
         ];
 
         for &case in CASES {
-            assert!(dbg!(case.parse::<MagnetDate>()).is_ok());
+            let uri = parse(case);
+            assert_eq!(uri.to_string(), case);
+            assert_eq!(parse(&uri.to_string()), uri);
         }
+
+        // Non-canonical input comes out canonical, and stays that way.
+        let uri = parse(&format!(
+            "magnet:?dn=Sample+Release&xt=urn:btih:{BASE32}&tr=udp://tracker.example:1337"
+        ));
+        let canonical = uri.to_string();
+        assert_eq!(
+            canonical,
+            format!("magnet:?xt=urn:btih:{HEX}&dn=Sample%20Release&tr=udp%3A%2F%2Ftracker.example%3A1337")
+        );
+        assert_eq!(parse(&canonical).to_string(), canonical);
+        assert_eq!(parse(&canonical), uri);
+    }
+
+    #[test]
+    fn rejects_bad_uris() {
+        assert!(matches!(
+            "https://example.com".parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::Scheme(_))
+        ));
+        assert!(matches!(
+            "magnet:?dn=Nothing".parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::NoInfoHash(_))
+        ));
+        assert!(matches!(
+            "magnet:?xt=urn:btih:F657AE".parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::BadInfoHash(_))
+        ));
+        assert!(matches!(
+            format!("magnet:?xt=urn:btih:{}", "Z".repeat(40)).parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::BadInfoHash(_))
+        ));
+        assert!(matches!(
+            "magnet:?xt=urn:btmh:1114aabb".parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::BadInfoHash(_))
+        ));
+        assert!(matches!(
+            format!("magnet:?xt=urn:btih:{HEX}&xl=big").parse::<MagnetUri>(),
+            Err(ParseMagnetUriErr::BadLength(_))
+        ));
     }
 }
@@ -1,85 +1,748 @@
 mod args;
+mod config;
 mod context;
+mod dedup;
+mod filter;
 mod history;
 mod magnet;
+mod mirror;
+mod output;
+mod profile;
+mod recording;
+mod schedule;
+mod sink;
+mod size;
+mod source;
+#[cfg(test)]
+mod testing;
+mod tracker;
 mod wait;
 
-use std::{fmt::Write, fs, io};
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Instant,
+};
 
-use args::Args;
-use context::Context;
+use args::{
+    start_of_day, ArgDate, Args, Cli, Command, HistoryArgs, HistoryCommand, Store, WatchArgs,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use config::{Config, Feed};
+use context::{Context, LayoutErr};
+use dedup::Dedup;
+use filter::Rule;
 use hashbrown::HashSet;
-use history::History;
+use history::{History, Query};
 use magnet::Magnet;
+use mirror::Mirrors;
+use profile::Profile;
+use schedule::Schedule;
+use sink::{Sink, SinkConfig};
+use size::Size;
 use unicase::UniCase;
-use wait::Waiter;
+use wait::{HostWaiter, Waiter};
+
+const DEFAULT_JOBS: usize = 4;
+
+/// How much of a run succeeded
+///
+/// The exit code distinguishes a run where some feeds failed from one where nothing worked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    Complete,
+    Partial,
+    Failed,
+}
+
+impl Outcome {
+    fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Complete => 0,
+            Outcome::Failed => 1,
+            Outcome::Partial => 2,
+        }
+    }
+}
 
 fn main() {
-    if let Err(e) = run(&Args::parse()) {
+    let outcome = match Cli::parse().into_command() {
+        Command::Run(args) => run(&args),
+        Command::Watch(args) => watch(&args),
+        Command::History(args) => history(&args),
+    };
+    let outcome = outcome.unwrap_or_else(|e| {
         eprintln!("{e}");
-        std::process::exit(1);
+        Outcome::Failed
+    });
+    std::process::exit(outcome.exit_code());
+}
+
+/// Everything a run needs, set up once so `watch` can poll over and over
+struct Session<'a> {
+    args: &'a Args,
+    config: &'a Config,
+    context: Context,
+    history: History,
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+/// The result of polling some feeds once
+struct Cycle<'a> {
+    magnets: Vec<Magnet>,
+    served_by: Vec<Served<'a>>,
+    failures: Vec<(&'a str, anyhow::Error)>,
+    /// Failures after polling, which `watch` reports and carries on from.
+    errors: Vec<(&'static str, anyhow::Error)>,
+}
+
+/// How one feed's poll went
+struct Served<'a> {
+    feed: &'a str,
+    mirror: String,
+    /// New magnets, after filtering.
+    count: usize,
+    pages: usize,
+    /// Rows which couldn't be read.
+    skipped: usize,
+}
+
+impl<'a> Session<'a> {
+    fn new(args: &'a Args, config: &'a Config, profile: &Profile) -> anyhow::Result<Self> {
+        let waiter = args
+            .wait
+            .or(config.defaults.wait)
+            .map(Waiter::with_wait)
+            .unwrap_or_default();
+        let context = Context::new(
+            Mirrors::new(args.mirrors.iter().chain(&config.defaults.mirrors)),
+            HostWaiter::new(waiter),
+            args.recording(),
+        );
+
+        let history = load_history(&args.store, profile)?;
+        let sinks: Vec<_> = config.sinks.iter().map(SinkConfig::build).collect();
+        let now = Utc::now();
+        for sink in &sinks {
+            history.add_sink(sink.name(), now)?;
+        }
+
+        Ok(Session {
+            args,
+            config,
+            context,
+            history,
+            sinks,
+        })
+    }
+
+    /// Fetches and filters new magnets from the given feeds, marking them seen in history.
+    ///
+    /// Feeds which fail are reported in the cycle; only a history error fails the whole poll.
+    fn poll(&mut self, feeds: &[&'a Feed]) -> anyhow::Result<Cycle<'a>> {
+        let Session {
+            args,
+            config,
+            context,
+            history,
+            ..
+        } = self;
+        let (args, config) = (*args, *config);
+
+        let mut candidates = Vec::new();
+        let mut accepted = HashSet::new();
+        let mut served_by = Vec::new();
+        let mut failures = Vec::new();
+
+        let started = Utc::now();
+        let jobs = args.jobs.or(config.defaults.jobs).unwrap_or(DEFAULT_JOBS);
+        let results = context.extract_all(
+            feeds,
+            |feed| feed.page_limit(args, &config.defaults),
+            |feed| feed.timezone(&config.defaults),
+            |feed, magnet| {
+                let last_run = history.last_run(feed.name());
+                // A history error only costs another page here; filtering will report it.
+                magnet.date < feed.take_after(args, &config.defaults, last_run)
+                    || history.contains(magnet).unwrap_or_default()
+            },
+            jobs,
+        );
+
+        // Everything from here on runs in feed order, so the listing and history come out the
+        // same no matter which feed finished first.
+        for (&feed, recent) in feeds.iter().zip(results) {
+            let recent = match recent {
+                Ok(recent) => recent,
+                Err(e) => {
+                    failures.push((feed.name(), e));
+                    continue;
+                }
+            };
+            // Stopping at the page limit with everything still new means there may be more we
+            // haven't seen, so next run should look just as far back.
+            if recent.caught_up {
+                history.finish_run(feed.name(), started);
+            } else {
+                eprintln!(
+                    "{}: everything up to the page limit ({}) was new; raise --pages to look \
+                     further back",
+                    feed.name(),
+                    recent.pages
+                );
+            }
+
+            candidates.extend(accept(
+                feed,
+                recent.magnets,
+                args,
+                config,
+                history,
+                &mut accepted,
+            ));
+            served_by.push(Served {
+                feed: feed.name(),
+                mirror: recent.mirror,
+                // Counted below, once duplicates and magnets seen before are out.
+                count: 0,
+                pages: recent.pages,
+                skipped: recent.skipped,
+            });
+        }
+
+        let mut magnets = emit(
+            history,
+            candidates,
+            config.defaults.dedup(args),
+            args.explain,
+        )?;
+        for served in &mut served_by {
+            served.count = magnets
+                .iter()
+                .filter(|magnet| magnet.feed == served.feed)
+                .count();
+        }
+
+        for magnet in &mut magnets {
+            config.defaults.trackers.apply(magnet);
+        }
+
+        Ok(Cycle {
+            magnets,
+            served_by,
+            failures,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Writes the listing, drops new magnets in the magnet directory and saves history.
+    fn publish(&mut self, listing: &mut [Magnet], new: &[Magnet]) -> anyhow::Result<()> {
+        let (args, config) = (self.args, self.config);
+
+        listing.sort_by(|a, b| {
+            a.category
+                .cmp(&b.category)
+                .then_with(|| UniCase::new(&a.text).cmp(&UniCase::new(&b.text)))
+        });
+        output::write(
+            args.format.or(config.defaults.format).unwrap_or_default(),
+            args.output().or(config.defaults.output.as_deref()),
+            listing,
+        )?;
+        if let Some(dir) = args
+            .magnet_dir
+            .as_ref()
+            .or(config.defaults.magnet_dir.as_ref())
+        {
+            output::write_magnet_dir(dir, config.defaults.magnet_subfolders, new)?;
+        }
+
+        // History is kept for the widest window any feed looked at.
+        let history_limit = config
+            .feeds
+            .iter()
+            .map(|feed| self.cutoff(feed))
+            .min()
+            .unwrap_or_else(|| args.take_after(config.defaults.timezone()));
+        self.history.write(history_limit)?;
+        Ok(())
+    }
+
+    fn deliver(&self) -> anyhow::Result<usize> {
+        deliver(self.config, &self.history, &self.sinks)
+    }
+
+    /// The oldest upload a feed looks at this run.
+    fn cutoff(&self, feed: &Feed) -> DateTime<Utc> {
+        let last_run = self.history.last_run(feed.name());
+        feed.take_after(self.args, &self.config.defaults, last_run)
+    }
+
+    /// Whether a magnet is still within its feed's window, as a fresh run would see it.
+    fn is_current(&self, magnet: &Magnet) -> bool {
+        self.config
+            .feeds
+            .iter()
+            .find(|feed| feed.name() == magnet.feed)
+            .is_some_and(|feed| magnet.date >= self.cutoff(feed))
+    }
+}
+
+impl Cycle<'_> {
+    fn report(&self) {
+        for served in &self.served_by {
+            let (name, mirror, count) = (served.feed, &served.mirror, served.count);
+            let pages = match served.pages {
+                1 => "1 page".to_string(),
+                n => format!("{n} pages"),
+            };
+            match served.skipped {
+                0 => eprintln!("{name}: {count} new from {pages} via {mirror}"),
+                skipped => eprintln!(
+                    "{name}: {count} new from {pages} via {mirror} ({skipped} rows skipped)"
+                ),
+            }
+        }
+    }
+
+    fn all_failed(&self, feed_count: usize) -> bool {
+        !self.failures.is_empty() && self.failures.len() == feed_count
+    }
+
+    fn report_failures(&self, feed_count: usize) {
+        if !self.failures.is_empty() {
+            eprintln!("\n{} of {feed_count} feeds failed:", self.failures.len());
+            for (name, e) in &self.failures {
+                // Layout errors already name their feed and where the page went.
+                if e.downcast_ref::<LayoutErr>().is_some() {
+                    eprintln!("{e}");
+                } else {
+                    eprintln!("{name}: {e}");
+                }
+            }
+        }
+        for (stage, e) in &self.errors {
+            eprintln!("{stage} failed: {e:#}");
+        }
+    }
+}
+
+fn run(args: &Args) -> anyhow::Result<Outcome> {
+    let profile = Profile::new(args.store.profile.as_deref())?;
+    let config = Config::load(args.config_path(&profile))?;
+    let mut session = Session::new(args, &config, &profile)?;
+
+    let feeds: Vec<_> = config.feeds.iter().collect();
+    let mut cycle = session.poll(&feeds)?;
+    let new = cycle.magnets.clone();
+    // With nothing fetched, the last good listing is worth more than an empty one.
+    if !cycle.all_failed(feeds.len()) {
+        session.publish(&mut cycle.magnets, &new)?;
+    }
+    cycle.report();
+    let undelivered = session.deliver()?;
+    cycle.report_failures(feeds.len());
+
+    if cycle.failures.is_empty() && undelivered == 0 {
+        Ok(Outcome::Complete)
+    } else if cycle.all_failed(feeds.len()) {
+        Ok(Outcome::Failed)
+    } else {
+        Ok(Outcome::Partial)
+    }
+}
+
+/// Stays resident, polling each feed on its own interval until SIGINT or SIGTERM.
+///
+/// The listing holds everything found since the watch began that's still within its feed's
+/// window. Errors are reported and the next cycle goes ahead regardless.
+fn watch(args: &WatchArgs) -> anyhow::Result<Outcome> {
+    let (shutdown, stop) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = shutdown.send(());
+    })?;
+
+    let profile = Profile::new(args.run.store.profile.as_deref())?;
+    let config = Config::load(args.run.config_path(&profile))?;
+    let mut session = Session::new(&args.run, &config, &profile)?;
+
+    let intervals = config
+        .feeds
+        .iter()
+        .map(|feed| feed.interval(args.interval, &config.defaults))
+        .collect();
+    let mut schedule = Schedule::new(intervals, Instant::now());
+    let mut listing = Vec::new();
+
+    loop {
+        let feeds: Vec<_> = schedule
+            .due(Instant::now())
+            .into_iter()
+            .map(|idx| &config.feeds[idx])
+            .collect();
+
+        if !feeds.is_empty() {
+            match session.poll(&feeds) {
+                Ok(mut cycle) => {
+                    listing.extend(cycle.magnets.iter().cloned());
+                    listing.retain(|magnet| session.is_current(magnet));
+                    if let Err(e) = session.publish(&mut listing, &cycle.magnets) {
+                        cycle.errors.push(("publishing", e));
+                    }
+                    cycle.report();
+                    if let Err(e) = session.deliver() {
+                        cycle.errors.push(("delivery", e));
+                    }
+                    cycle.report_failures(feeds.len());
+                }
+                Err(e) => eprintln!("polling failed: {e:#}"),
+            }
+        }
+
+        // A signal during a cycle is picked up here, once history is safely written.
+        match stop.recv_timeout(schedule.until_next(Instant::now())) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    eprintln!("shutting down");
+    Ok(Outcome::Complete)
+}
+
+/// Runs one feed's magnets through its rules, recording each in history.
+///
+/// `accepted` holds the magnets earlier feeds have let through this cycle. Those are skipped
+/// here, but one an earlier feed's own rules turned away may still be taken by this feed.
+fn accept(
+    feed: &Feed,
+    magnets: Vec<Magnet>,
+    args: &Args,
+    config: &Config,
+    history: &mut History,
+    accepted: &mut HashSet<String>,
+) -> Vec<Magnet> {
+    let last_run = history.last_run(feed.name());
+    let mut candidates = Vec::new();
+    for magnet in magnets {
+        history.record(&magnet);
+
+        if let Err(rejection) = feed.check(&magnet, args, &config.defaults, last_run) {
+            if args.explain {
+                eprintln!("{}: dropped {:?}: {rejection}", feed.name(), magnet.text);
+            }
+            continue;
+        }
+
+        let key = magnet.infohash().unwrap_or_else(|| magnet.link.clone());
+        if !accepted.insert(key) {
+            if args.explain {
+                eprintln!("{}: dropped {:?}: already taken", feed.name(), magnet.text);
+            }
+            continue;
+        }
+
+        candidates.push(magnet);
     }
+    candidates
 }
 
-fn run(args: &Args) -> anyhow::Result<()> {
-    let context = Context::new();
-    let links = fs::read_to_string(&args.path)?;
+/// Picks the magnets to list from those which passed each feed's filters, marking them emitted.
+///
+/// Near-duplicates are dropped before history sees them, so only the magnet kept is marked
+/// emitted. Ones already listed still take part: the upload we skip today, because its better
+/// copy was listed yesterday, shouldn't turn up on its own tomorrow.
+fn emit(
+    history: &mut History,
+    mut candidates: Vec<Magnet>,
+    dedup: Option<&[Rule]>,
+    explain: bool,
+) -> anyhow::Result<Vec<Magnet>> {
+    if let Some(quality) = dedup {
+        for (magnet, kept) in Dedup::new(quality).dedup(&mut candidates) {
+            if explain {
+                eprintln!(
+                    "{}: dropped {:?}: duplicate of {kept:?}",
+                    magnet.feed, magnet.text
+                );
+            }
+        }
+    }
 
-    let mut waiter = args.wait.map(Waiter::with_wait).unwrap_or_default();
-    let mut history = History::load()?;
     let mut magnets = Vec::new();
-    let mut unique_magnet_filter = HashSet::new();
-
-    for url in links.lines() {
-        let mut recent = context.extract_recent(
-            url,
-            args.page_limit(),
-            &mut unique_magnet_filter,
-            &mut waiter,
-        )?;
+    for magnet in candidates {
+        if !history.filter(&magnet)? {
+            if explain {
+                eprintln!("{}: dropped {:?}: already seen", magnet.feed, magnet.text);
+            }
+            continue;
+        }
+        magnets.push(magnet);
+    }
+    Ok(magnets)
+}
+
+/// Hands each sink the listed magnets it doesn't have yet, as written to history. Returns the
+/// number of magnets which couldn't be sent; they'll be tried again next run.
+fn deliver(config: &Config, history: &History, sinks: &[Box<dyn Sink>]) -> anyhow::Result<usize> {
+    let mut failed = 0;
+    for sink in sinks {
+        let mut sent = 0;
+        for record in history.undelivered(sink.name())? {
+            let mut magnet = record.magnet();
+            config.defaults.trackers.apply(&mut magnet);
 
-        recent.retain(|magnet| magnet.date >= args.take_after() && history.filter(magnet));
-        magnets.extend(recent);
+            let delivery = config
+                .feeds
+                .iter()
+                .find(|feed| feed.name() == magnet.feed)
+                .map(|feed| feed.delivery(&config.defaults))
+                .unwrap_or_default();
+            match sink.send(&magnet, &delivery) {
+                Ok(()) => {
+                    history.mark_delivered(&record.infohash, sink.name())?;
+                    sent += 1;
+                }
+                Err(e) => {
+                    eprintln!("{}: failed to send {:?}: {e:#}", sink.name(), magnet.text);
+                    failed += 1;
+                }
+            }
+        }
+        eprintln!("{}: sent {sent}", sink.name());
+    }
+    Ok(failed)
+}
+
+fn history(args: &HistoryArgs) -> anyhow::Result<Outcome> {
+    let profile = Profile::new(args.store.profile.as_deref())?;
+    let mut history = load_history(&args.store, &profile)?;
+
+    match &args.command {
+        HistoryCommand::List {
+            feed,
+            since,
+            until,
+            all,
+        } => {
+            let query = Query {
+                feed: feed.as_deref(),
+                since: since.map(ArgDate::into_inner),
+                until: until.map(ArgDate::into_inner),
+                all: *all,
+                ..Default::default()
+            };
+            print_records(&history, &query)?;
+        }
+        HistoryCommand::Search { text, all } => {
+            let query = Query {
+                text: Some(text),
+                all: *all,
+                ..Default::default()
+            };
+            print_records(&history, &query)?;
+        }
+        HistoryCommand::Forget { infohash } => {
+            if !history.forget(infohash)? {
+                anyhow::bail!("not in history: {infohash}");
+            }
+            eprintln!("forgot {infohash}");
+        }
+        HistoryCommand::Prune { before, config } => {
+            // The day begins where it does for the filter window: in the site's timezone.
+            let path = config.clone().unwrap_or_else(|| profile.config_path());
+            let timezone = if path.exists() {
+                Config::load(&path)?.defaults.timezone()
+            } else {
+                Tz::UTC
+            };
+            let count = history.prune(start_of_day(before.into_inner(), timezone))?;
+            eprintln!("removed {count} uploaded before {}", before.into_inner());
+        }
+        HistoryCommand::Stats => {
+            for stats in history.stats()? {
+                println!(
+                    "{}\t{} listed\t{} seen\tlast seen {}",
+                    display_feed(&stats.feed),
+                    stats.emitted,
+                    stats.seen,
+                    stats.last_seen.date_naive(),
+                );
+            }
+        }
     }
 
-    magnets.sort_unstable_by(|a, b| UniCase::new(&a.text).cmp(&UniCase::new(&b.text)));
-    write_html(args, &magnets)?;
-    history.write(args.take_after())?;
+    Ok(Outcome::Complete)
+}
 
+/// Opens the history for a profile. Only the default profile inherits history from before
+/// profiles existed.
+fn load_history(store: &Store, profile: &Profile) -> anyhow::Result<History> {
+    match &store.history {
+        Some(path) => History::load(path, None),
+        None if profile.is_default() => {
+            History::load(profile.history_path(), Some(&history::legacy_path()?))
+        }
+        None => History::load(profile.history_path(), None),
+    }
+}
+
+fn print_records(history: &History, query: &Query) -> anyhow::Result<()> {
+    for record in history.query(query)? {
+        let filtered = if record.emitted { "" } else { "\t(filtered)" };
+        println!(
+            "{}\t{}\t{}\t{}\t{}{filtered}",
+            record.first_seen.date_naive(),
+            display_feed(&record.feed),
+            record.infohash,
+            Size::from_bytes(record.size),
+            record.title,
+        );
+    }
     Ok(())
 }
 
-fn write_html(args: &Args, magnets: &[Magnet]) -> io::Result<()> {
-    static STYLE: &str = include_str!("../resource/style.css");
+/// Records imported from the old history file have no feed.
+fn display_feed(feed: &str) -> &str {
+    if feed.is_empty() {
+        "(unknown)"
+    } else {
+        feed
+    }
+}
 
-    let mut buf = String::new();
-    writeln!(buf, "<style>\n{STYLE}\n</style>").expect("no way can this break");
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
 
-    writeln!(buf, "<body>").unwrap();
-    magnets
-        .iter()
-        .for_each(|magnet| format_line(&mut buf, magnet));
-    writeln!(buf, "</body>").unwrap();
-
-    match args.output() {
-        Some(path) => fs::write(path, &buf),
-        None => fs::write("listing.html", &buf),
-    }
-}
-
-fn format_line(buf: &mut String, magnet: &Magnet) {
-    let date = magnet.date;
-    let size = &magnet.size;
-    let link = &magnet.link;
-    let text = &magnet.text;
-
-    writeln!(
-        buf,
-        include_str!("../resource/template.html"),
-        date = date,
-        size = size,
-        link = link,
-        text = text,
-    )
-    .unwrap()
+    use chrono::{TimeZone, Utc};
+
+    use hashbrown::HashSet;
+
+    use crate::{
+        args::Args,
+        config::{Config, Feed},
+        filter::Rule,
+        history::History,
+        magnet::Magnet,
+        sink::{Delivery, Sink},
+        testing::TempDir,
+    };
+
+    /// A client which is down for its first few sends
+    struct Flaky {
+        failures: Cell<usize>,
+        sent: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Sink for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn send(&self, magnet: &Magnet, _: &Delivery) -> anyhow::Result<()> {
+            if self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                anyhow::bail!("connection refused");
+            }
+            self.sent.borrow_mut().push(magnet.link.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn uploads_one_feed_rejects_are_left_for_the_next() {
+        let dir = TempDir::new("accept");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        let args = <Args as clap::Parser>::parse_from(["yogo", "config.toml"]);
+        let config = Config::default();
+        let feed = |name: &str, include: &str| Feed {
+            name: Some(name.into()),
+            include: vec![include.parse().unwrap()],
+            ..Default::default()
+        };
+        let (uhd, sample) = (feed("uhd", "2160p"), feed("sample", "sample"));
+        let upload = Magnet {
+            text: "Sample Release 1080p".into(),
+            date: Utc::now(),
+            ..Magnet::sample()
+        };
+
+        let mut accepted = HashSet::new();
+        let taken = |feed, history: &mut History, accepted: &mut HashSet<String>| {
+            super::accept(
+                feed,
+                vec![upload.clone()],
+                &args,
+                &config,
+                history,
+                accepted,
+            )
+            .len()
+        };
+        assert_eq!(taken(&uhd, &mut history, &mut accepted), 0);
+        assert_eq!(taken(&sample, &mut history, &mut accepted), 1);
+        // Once a feed has taken it, it's not listed twice.
+        assert_eq!(taken(&sample, &mut history, &mut accepted), 0);
+    }
+
+    #[test]
+    fn failed_deliveries_are_retried() {
+        let dir = TempDir::new("retry");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        let config = Config::default();
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let sinks: Vec<Box<dyn Sink>> = vec![Box::new(Flaky {
+            failures: Cell::new(1),
+            sent: sent.clone(),
+        })];
+        history.add_sink("flaky", Utc::now()).unwrap();
+
+        history.filter(&Magnet::sample()).unwrap();
+        history
+            .write(Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap())
+            .unwrap();
+        assert_eq!(super::deliver(&config, &history, &sinks).unwrap(), 1);
+
+        // The magnet is listed already, so the next poll has nothing new, but it's still owed.
+        assert_eq!(super::deliver(&config, &history, &sinks).unwrap(), 0);
+        assert_eq!(super::deliver(&config, &history, &sinks).unwrap(), 0);
+        assert_eq!(*sent.borrow(), [Magnet::sample().link]);
+    }
+
+    #[test]
+    fn duplicates_dropped_are_not_emitted() {
+        let dir = TempDir::new("emit");
+        let mut history = History::load(dir.join("history.sqlite"), None).unwrap();
+        let quality: Vec<Rule> = vec!["1080p".parse().unwrap()];
+
+        let best = Magnet {
+            text: "Some Release 1080p".into(),
+            link: format!("magnet:?xt=urn:btih:{}", "a".repeat(40)),
+            ..Magnet::sample()
+        };
+        let worse = Magnet {
+            text: "Some Release 720p".into(),
+            link: format!("magnet:?xt=urn:btih:{}", "b".repeat(40)),
+            ..Magnet::sample()
+        };
+        history.record(&best);
+        history.record(&worse);
+
+        let candidates = vec![worse.clone(), best.clone()];
+        let listed = super::emit(&mut history, candidates, Some(&quality), false).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].text, best.text);
+        assert!(history.contains(&best).unwrap());
+        assert!(!history.contains(&worse).unwrap());
+
+        // Next time the better copy has been listed already, and still keeps the other out.
+        let candidates = vec![worse.clone(), best.clone()];
+        let listed = super::emit(&mut history, candidates, Some(&quality), false).unwrap();
+        assert!(listed.is_empty());
+        assert!(!history.contains(&worse).unwrap());
+    }
 }
@@ -0,0 +1,97 @@
+use std::sync::Mutex;
+
+use hashbrown::HashSet;
+use reqwest::blocking::Response;
+
+/// Base urls for an index, in order of preference
+///
+/// The bases are all hosts for the same site, and only feeds on one of them fail over to the
+/// rest. Mirrors which have failed during this run are remembered and tried only after the
+/// healthy ones, so a dead domain costs us one failed request rather than one per page.
+#[derive(Debug, Default)]
+pub struct Mirrors {
+    bases: Vec<String>,
+    failed: Mutex<HashSet<String>>,
+}
+
+impl Mirrors {
+    pub fn new(bases: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            bases: bases
+                .into_iter()
+                .map(|base| base.as_ref().trim_end_matches('/').into())
+                .collect(),
+            failed: Default::default(),
+        }
+    }
+
+    /// Base urls to try for a feed whose own url begins with `origin`.
+    ///
+    /// A feed on one of the mirrors may be served by any of them, in the configured order. Any
+    /// other feed is a different site, and is only ever fetched from its own origin.
+    pub fn candidates(&self, origin: &str) -> Vec<String> {
+        if !self.bases.iter().any(|base| base == origin) {
+            return vec![origin.into()];
+        }
+        let mut candidates = self.bases.clone();
+
+        let failed = self.failed.lock().unwrap();
+        candidates.sort_by_key(|base| failed.contains(base));
+        candidates
+    }
+
+    pub fn mark_failed(&self, base: &str) {
+        self.failed.lock().unwrap().insert(base.into());
+    }
+
+    pub fn mark_healthy(&self, base: &str) {
+        self.failed.lock().unwrap().remove(base);
+    }
+}
+
+/// The ways a request can fail that suggest the mirror, rather than the request, is at fault.
+pub fn should_fail_over(result: &reqwest::Result<Response>) -> Option<String> {
+    match result {
+        Ok(response) if response.status().is_server_error() => {
+            Some(format!("server error: {}", response.status()))
+        }
+        Ok(_) => None,
+        Err(e) if e.is_connect() || e.is_timeout() => Some(e.to_string()),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mirrors;
+
+    #[test]
+    fn failed_mirrors_are_tried_last() {
+        let mirrors = Mirrors::new(["https://a.example/", "https://b.example"]);
+        assert_eq!(
+            mirrors.candidates("https://b.example"),
+            ["https://a.example", "https://b.example"]
+        );
+
+        mirrors.mark_failed("https://a.example");
+        assert_eq!(
+            mirrors.candidates("https://b.example"),
+            ["https://b.example", "https://a.example"]
+        );
+
+        mirrors.mark_healthy("https://a.example");
+        assert_eq!(
+            mirrors.candidates("https://b.example"),
+            ["https://a.example", "https://b.example"]
+        );
+    }
+
+    #[test]
+    fn other_sites_are_not_sent_to_mirrors() {
+        let mirrors = Mirrors::new(["https://a.example", "https://b.example"]);
+        assert_eq!(
+            mirrors.candidates("https://other.example"),
+            ["https://other.example"]
+        );
+    }
+}
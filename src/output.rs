@@ -0,0 +1,291 @@
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::magnet::Magnet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// a page of links for a browser
+    #[default]
+    Html,
+    /// an array of magnet objects
+    Json,
+    /// date,size,link,text
+    Csv,
+    /// an RSS 2.0 feed a torrent client can subscribe to
+    Rss,
+}
+
+impl Format {
+    fn default_path(self) -> &'static str {
+        match self {
+            Format::Html => "listing.html",
+            Format::Json => "listing.json",
+            Format::Csv => "listing.csv",
+            Format::Rss => "listing.xml",
+        }
+    }
+}
+
+pub fn write(format: Format, output: Option<&str>, magnets: &[Magnet]) -> io::Result<()> {
+    let buf = match format {
+        Format::Html => format_html(magnets),
+        Format::Json => serde_json::to_string_pretty(magnets)?,
+        Format::Csv => format_csv(magnets)?,
+        Format::Rss => format_rss(magnets),
+    };
+
+    fs::write(output.unwrap_or_else(|| format.default_path()), buf)
+}
+
+/// Writes one `<infohash>.magnet` file per magnet, for clients which watch a directory.
+///
+/// With `per_feed`, each feed gets its own subdirectory. Each file is written under a temporary
+/// name and then renamed, so a client never picks up a partial file. Returns the number of files
+/// written.
+pub fn write_magnet_dir(dir: &Path, per_feed: bool, magnets: &[Magnet]) -> io::Result<usize> {
+    let mut count = 0;
+    for magnet in magnets {
+        let Some(infohash) = magnet.infohash() else {
+            continue;
+        };
+
+        let dir = match per_feed {
+            true => dir.join(folder_name(&magnet.feed)),
+            false => dir.to_owned(),
+        };
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("{infohash}.magnet"));
+        let temp = dir.join(format!(".{infohash}.magnet.tmp"));
+        fs::write(&temp, format!("{}\n", magnet.link))?;
+        fs::rename(&temp, &path)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Feed names are often urls, so anything but a few safe characters becomes an underscore.
+fn folder_name(feed: &str) -> PathBuf {
+    let name: String = feed
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c,
+            ' ' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+
+    match name.trim_matches('.') {
+        "" => PathBuf::from("_"),
+        name => PathBuf::from(name),
+    }
+}
+
+fn format_html(magnets: &[Magnet]) -> String {
+    static STYLE: &str = include_str!("../resource/style.css");
+
+    let mut buf = String::new();
+    writeln!(
+        buf,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">"
+    )
+    .expect("no way can this break");
+    writeln!(buf, "<style>\n{STYLE}\n</style>\n</head>").unwrap();
+
+    writeln!(buf, "<body>").unwrap();
+    let mut category = None;
+    for magnet in magnets {
+        if magnet.category.is_some() && magnet.category != category {
+            category = magnet.category.clone();
+            writeln!(buf, "<h2>{}</h2>", escape(category.as_deref().unwrap())).unwrap();
+        }
+        format_line(&mut buf, magnet);
+    }
+    writeln!(buf, "</body>\n</html>").unwrap();
+    buf
+}
+
+fn format_line(buf: &mut String, magnet: &Magnet) {
+    let date = magnet.date.format("%Y-%m-%d %H:%M");
+    let size = &magnet.size;
+    let link = escape(&magnet.link);
+    let text = escape(&magnet.text);
+    let kind = escape(magnet.kind.as_deref().unwrap_or("Uncategorized"));
+    let uploader = escape(magnet.uploader.as_deref().unwrap_or("Anonymous"));
+
+    writeln!(
+        buf,
+        include_str!("../resource/template.html"),
+        date = date,
+        size = size,
+        seeders = magnet.seeders,
+        leechers = magnet.leechers,
+        link = link,
+        text = text,
+        kind = kind,
+        uploader = uploader,
+    )
+    .unwrap()
+}
+
+fn format_csv(magnets: &[Magnet]) -> io::Result<String> {
+    // Same columns as resource/test-input.csv.
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["date", "size", "link", "text"])?;
+    for magnet in magnets {
+        let date = magnet.date.to_rfc3339();
+        let size = magnet.size.bytes().to_string();
+        writer.write_record([&date, &size, &magnet.link, &magnet.text])?;
+    }
+
+    let buf = writer
+        .into_inner()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(String::from_utf8(buf).expect("csv of utf-8 strings is utf-8"))
+}
+
+fn format_rss(magnets: &[Magnet]) -> String {
+    let mut buf = String::new();
+    writeln!(buf, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(buf, r#"<rss version="2.0">"#).unwrap();
+    writeln!(buf, "<channel>").unwrap();
+    writeln!(buf, "<title>yogo</title>").unwrap();
+    writeln!(buf, "<link>https://github.com/kcbyo/yogo</link>").unwrap();
+    writeln!(buf, "<description>Recent magnets</description>").unwrap();
+
+    for magnet in magnets {
+        let link = escape(&magnet.link);
+        writeln!(buf, "<item>").unwrap();
+        writeln!(buf, "<title>{}</title>", escape(&magnet.text)).unwrap();
+        writeln!(buf, "<link>{link}</link>").unwrap();
+        writeln!(buf, r#"<guid isPermaLink="false">{link}</guid>"#).unwrap();
+        writeln!(
+            buf,
+            r#"<enclosure url="{link}" length="{}" type="application/x-bittorrent"/>"#,
+            magnet.size.bytes()
+        )
+        .unwrap();
+        // RFC 822 style, with a zero-padded day as most feed readers expect.
+        let date = magnet.date.format("%a, %d %b %Y %H:%M:%S %z");
+        writeln!(buf, "<pubDate>{date}</pubDate>").unwrap();
+        if let Some(category) = &magnet.category {
+            writeln!(buf, "<category>{}</category>", escape(category)).unwrap();
+        }
+        writeln!(buf, "</item>").unwrap();
+    }
+
+    writeln!(buf, "</channel>\n</rss>").unwrap();
+    buf
+}
+
+/// Escapes text for use in HTML or XML, whether in element content or a quoted attribute.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::{magnet::Magnet, testing::TempDir};
+
+    fn magnets() -> Vec<Magnet> {
+        vec![Magnet {
+            text: "Fish & Chips <1080p>".into(),
+            link: "magnet:?xt=urn:btih:F657AEFD637DBCC4E3AC965DDB7CCA0C11BC157C&dn=Fish".into(),
+            seeders: 42,
+            leechers: 7,
+            uploader: Some("PornBaker".into()),
+            kind: Some("Porn > HD - Movies".into()),
+            category: Some("food".into()),
+            ..Magnet::sample()
+        }]
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let html = super::format_html(&magnets());
+        assert!(html.contains("Fish &amp; Chips &lt;1080p&gt;"));
+        assert!(html.contains("BC157C&amp;dn=Fish"));
+        assert!(html.contains("<h2>food</h2>"));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let json = serde_json::to_string(&magnets()).unwrap();
+        let magnets: Vec<Magnet> = serde_json::from_str(&json).unwrap();
+        assert_eq!(magnets[0].text, "Fish & Chips <1080p>");
+        assert_eq!(
+            magnets[0].date,
+            Utc.with_ymd_and_hms(2022, 5, 2, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn csv_matches_test_input_columns() {
+        let csv = super::format_csv(&magnets()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("date,size,link,text"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("2022-05-02T00:00:00+00:00,609117798,magnet:?xt=urn:btih:"));
+    }
+
+    #[test]
+    fn magnet_files_are_written_per_feed() {
+        let dir = TempDir::new("magnets");
+
+        let mut magnets = magnets();
+        magnets[0].feed = "https://thepiratebay10.org/user/PornBaker/".into();
+        assert_eq!(super::write_magnet_dir(&dir, true, &magnets).unwrap(), 1);
+
+        let feed_dir = dir.join("https___thepiratebay10.org_user_PornBaker_");
+        let file = feed_dir.join("f657aefd637dbcc4e3ac965ddb7cca0c11bc157c.magnet");
+        assert_eq!(
+            std::fs::read_to_string(file).unwrap(),
+            format!("{}\n", magnets[0].link)
+        );
+        assert_eq!(std::fs::read_dir(&feed_dir).unwrap().count(), 1);
+
+        assert_eq!(super::write_magnet_dir(&dir, false, &magnets).unwrap(), 1);
+        assert!(dir
+            .join("f657aefd637dbcc4e3ac965ddb7cca0c11bc157c.magnet")
+            .exists());
+    }
+
+    #[test]
+    fn folder_names_are_safe() {
+        assert_eq!(super::folder_name("deen").to_str(), Some("deen"));
+        assert_eq!(super::folder_name("a/b").to_str(), Some("a_b"));
+        assert_eq!(super::folder_name("../..").to_str(), Some("_"));
+        assert_eq!(super::folder_name("..").to_str(), Some("_"));
+    }
+
+    #[test]
+    fn rss_has_one_item_per_magnet() {
+        let rss = super::format_rss(&magnets());
+        assert_eq!(rss.matches("<item>").count(), 1);
+        assert!(rss.contains("<title>Fish &amp; Chips &lt;1080p&gt;</title>"));
+        assert!(rss.contains("<pubDate>Mon, 02 May 2022 00:00:00 +0000</pubDate>"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    env,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+
+/// Overrides the platform data directory, mostly so tests don't touch real profiles.
+pub const DATA_DIR_VAR: &str = "YOGO_DATA_DIR";
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// A named set of subscriptions with its own history
+///
+/// Each profile lives in its own directory under the platform data dir (e.g.
+/// `~/.local/share/yogo/<name>` on Linux), holding `config.toml` and `history.sqlite`.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    name: String,
+    dir: PathBuf,
+}
+
+impl Profile {
+    pub fn new(name: Option<&str>) -> io::Result<Self> {
+        Self::in_dir(&data_dir(env::var_os(DATA_DIR_VAR))?, name)
+    }
+
+    fn in_dir(data_dir: &Path, name: Option<&str>) -> io::Result<Self> {
+        let name = name.unwrap_or(DEFAULT_PROFILE);
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("bad profile name: {name:?}"),
+            ));
+        }
+
+        Ok(Profile {
+            name: name.into(),
+            dir: data_dir.join(name),
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.dir.join("config.toml")
+    }
+
+    pub fn history_path(&self) -> PathBuf {
+        self.dir.join("history.sqlite")
+    }
+}
+
+/// The directory profiles live in: `overridden`, if set, otherwise the platform data dir.
+fn data_dir(overridden: Option<OsString>) -> io::Result<PathBuf> {
+    if let Some(dir) = overridden {
+        return Ok(dir.into());
+    }
+
+    ProjectDirs::from("", "", "yogo")
+        .map(|dirs| dirs.data_dir().to_owned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "data dir not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Profile;
+
+    #[test]
+    fn profiles_get_their_own_files() {
+        let data = Path::new("/data/yogo");
+        let default = Profile::in_dir(data, None).unwrap();
+        let work = Profile::in_dir(data, Some("work")).unwrap();
+
+        assert!(default.is_default());
+        assert_eq!(default.history_path(), data.join("default/history.sqlite"));
+        assert_eq!(work.config_path(), data.join("work/config.toml"));
+        assert_eq!(work.history_path(), data.join("work/history.sqlite"));
+    }
+
+    #[test]
+    fn data_dir_can_be_overridden() {
+        let data = super::data_dir(Some("/tmp/yogo-data".into())).unwrap();
+        let profile = Profile::in_dir(&data, Some("work")).unwrap();
+        assert_eq!(
+            profile.history_path(),
+            Path::new("/tmp/yogo-data/work/history.sqlite")
+        );
+    }
+
+    #[test]
+    fn profile_names_stay_inside_the_data_dir() {
+        let data = Path::new("/data/yogo");
+        for name in ["", "..", "../other", "a/b", r"a\b"] {
+            assert!(Profile::in_dir(data, Some(name)).is_err(), "{name:?}");
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Everything but these is escaped in page file names, so each url gets a name of its own.
+const FILE_NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+/// Whether fetched pages are saved to, or served from, a directory
+///
+/// Recording keeps a copy of every page a run fetched. Replaying serves those copies instead of
+/// going to the network, so parsing can be checked offline against exactly what a site sent.
+#[derive(Clone, Debug, Default)]
+pub enum Recording {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Where the page for a url is kept, e.g. `https%3A%2F%2Ftpb.example%2Fuser%2Fx%2F1%2F3.html`.
+pub fn page_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(format!("{}.html", utf8_percent_encode(url, FILE_NAME)))
+}
+
+pub fn save(dir: &Path, url: &str, text: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = page_path(dir, url);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// Reads a recorded page. Returns None if the url was never recorded.
+pub fn load(dir: &Path, url: &str) -> io::Result<Option<String>> {
+    match fs::read_to_string(page_path(dir, url)) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::testing::TempDir;
+
+    #[test]
+    fn urls_get_their_own_files() {
+        let dir = Path::new("pages");
+        let path = super::page_path(dir, "https://tpb.example/search/James%20Deen/1/3/0");
+        assert_eq!(
+            path,
+            dir.join("https%3A%2F%2Ftpb.example%2Fsearch%2FJames%2520Deen%2F1%2F3%2F0.html")
+        );
+        assert_ne!(
+            super::page_path(dir, "https://tpb.example/a/b"),
+            super::page_path(dir, "https://tpb.example/a_b")
+        );
+    }
+
+    #[test]
+    fn pages_round_trip() {
+        let dir = TempDir::new("pages");
+        let url = "https://tpb.example/user/x/1/3";
+
+        assert_eq!(super::load(&dir, url).unwrap(), None);
+        super::save(&dir, url, "<html></html>").unwrap();
+        assert_eq!(
+            super::load(&dir, url).unwrap().as_deref(),
+            Some("<html></html>")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+/// When each feed is next due under `watch`
+///
+/// Feeds are indexed in config order. Every feed is due as soon as the schedule starts; after
+/// that each comes round again one interval after it was last taken.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    next: Vec<Instant>,
+    intervals: Vec<Duration>,
+}
+
+impl Schedule {
+    pub fn new(intervals: Vec<Duration>, now: Instant) -> Self {
+        Schedule {
+            next: vec![now; intervals.len()],
+            intervals,
+        }
+    }
+
+    /// Takes the feeds due at `now`, in order, and schedules their next poll.
+    pub fn due(&mut self, now: Instant) -> Vec<usize> {
+        let mut due = Vec::new();
+        for (idx, next) in self.next.iter_mut().enumerate() {
+            if *next <= now {
+                *next = now + self.intervals[idx];
+                due.push(idx);
+            }
+        }
+        due
+    }
+
+    /// How long until the next feed is due; forever if there are no feeds.
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.next
+            .iter()
+            .min()
+            .map(|next| next.saturating_duration_since(now))
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Schedule;
+
+    #[test]
+    fn feeds_come_round_on_their_own_intervals() {
+        let minute = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut schedule = Schedule::new(vec![minute, 3 * minute], start);
+
+        assert_eq!(schedule.due(start), [0, 1]);
+        assert!(schedule.due(start).is_empty());
+        assert_eq!(schedule.until_next(start), minute);
+
+        assert_eq!(schedule.due(start + minute), [0]);
+        assert_eq!(schedule.due(start + 2 * minute), [0]);
+        assert_eq!(schedule.due(start + 3 * minute), [0, 1]);
+        assert_eq!(schedule.until_next(start + 3 * minute), minute);
+    }
+
+    #[test]
+    fn late_polls_are_not_made_up() {
+        let minute = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut schedule = Schedule::new(vec![minute], start);
+        schedule.due(start);
+
+        // A slow cycle doesn't leave a backlog of polls to catch up on.
+        assert_eq!(schedule.due(start + 5 * minute), [0]);
+        assert!(schedule.due(start + 5 * minute).is_empty());
+        assert_eq!(schedule.until_next(start + 5 * minute), minute);
+    }
+}
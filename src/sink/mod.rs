@@ -0,0 +1,133 @@
+mod qbittorrent;
+mod transmission;
+
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+use crate::magnet::Magnet;
+
+/// A torrent client yogo can hand magnets to
+pub trait Sink {
+    /// Identifies the sink in history and in messages.
+    fn name(&self) -> &str;
+
+    fn send(&self, magnet: &Magnet, delivery: &Delivery) -> anyhow::Result<()>;
+}
+
+/// How a client should file a magnet, from the settings of the feed which produced it
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Delivery<'a> {
+    pub category: Option<&'a str>,
+    pub save_path: Option<&'a str>,
+    pub paused: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    Qbittorrent,
+    Transmission,
+}
+
+/// A `[[sink]]` table from the config
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SinkConfig {
+    pub kind: SinkKind,
+    /// Defaults to the url. History remembers deliveries by name, so a renamed sink starts
+    /// afresh, owed only what's listed from then on.
+    pub name: Option<String>,
+    /// e.g. `http://localhost:8080` for qBittorrent or `http://localhost:9091` for Transmission.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl SinkConfig {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
+    }
+
+    pub fn build(&self) -> Box<dyn Sink> {
+        match self.kind {
+            SinkKind::Qbittorrent => Box::new(qbittorrent::Qbittorrent::new(self)),
+            SinkKind::Transmission => Box::new(transmission::Transmission::new(self)),
+        }
+    }
+}
+
+fn build_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("unreachable: client")
+}
+
+/// A local HTTP server standing in for a torrent client, or a site
+#[cfg(test)]
+pub mod mock {
+    use std::{
+        sync::mpsc::{self, Receiver},
+        thread,
+    };
+
+    use tiny_http::{Response, Server};
+
+    use crate::magnet::Magnet;
+
+    pub fn magnet() -> Magnet {
+        Magnet {
+            text: "Sample Release 2022 1080p".into(),
+            link: "magnet:?xt=urn:btih:f657aefd637dbcc4e3ac965ddb7cca0c11bc157c".into(),
+            category: Some("scenes".into()),
+            ..Magnet::sample()
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Request {
+        pub url: String,
+        pub headers: Vec<(String, String)>,
+        pub body: String,
+    }
+
+    impl Request {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// Serves one response per request, in order, and passes each request back for inspection.
+    pub fn serve(
+        responses: Vec<Response<std::io::Cursor<Vec<u8>>>>,
+    ) -> (String, Receiver<Request>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let mut request = server.recv().unwrap();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let _ = tx.send(Request {
+                    url: request.url().into(),
+                    headers: request
+                        .headers()
+                        .iter()
+                        .map(|header| (header.field.to_string(), header.value.to_string()))
+                        .collect(),
+                    body,
+                });
+                request.respond(response).unwrap();
+            }
+        });
+
+        (url, rx)
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::Mutex;
+
+use anyhow::{bail, Context};
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    header::{COOKIE, REFERER, SET_COOKIE},
+    StatusCode,
+};
+
+use super::{Delivery, Sink, SinkConfig};
+use crate::magnet::Magnet;
+
+/// qBittorrent's Web API (v2)
+pub struct Qbittorrent {
+    name: String,
+    base: String,
+    username: Option<String>,
+    password: Option<String>,
+    client: Client,
+    /// The SID cookie from logging in.
+    cookie: Mutex<Option<String>>,
+}
+
+impl Qbittorrent {
+    pub fn new(config: &SinkConfig) -> Self {
+        Self {
+            name: config.name().into(),
+            base: config.url.trim_end_matches('/').into(),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            client: super::build_client(),
+            cookie: Mutex::new(None),
+        }
+    }
+
+    /// Logs in, unless no username is configured (qBittorrent can be set to skip
+    /// authentication for local clients).
+    fn login(&self) -> anyhow::Result<Option<String>> {
+        let Some(username) = &self.username else {
+            return Ok(None);
+        };
+
+        let response = self
+            .post("/api/v2/auth/login")
+            .form(&[
+                ("username", username.as_str()),
+                ("password", self.password.as_deref().unwrap_or_default()),
+            ])
+            .send()?
+            .error_for_status()?;
+
+        let cookie = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next())
+            .find(|cookie| cookie.starts_with("SID="))
+            .map(String::from);
+
+        match (response.text()?.trim(), cookie) {
+            ("Ok.", Some(cookie)) => Ok(Some(cookie)),
+            _ => bail!("login refused for {username}"),
+        }
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        // The API refuses requests whose referer doesn't match its own host.
+        self.client
+            .post(format!("{}{path}", self.base))
+            .header(REFERER, &self.base)
+    }
+
+    fn add(&self, cookie: Option<&str>, form: &[(&str, &str)]) -> anyhow::Result<StatusCode> {
+        let mut request = self.post("/api/v2/torrents/add").form(form);
+        if let Some(cookie) = cookie {
+            request = request.header(COOKIE, cookie);
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+        if status == StatusCode::FORBIDDEN {
+            return Ok(status);
+        }
+
+        let text = response.error_for_status()?.text()?;
+        if text.trim() != "Ok." {
+            bail!("torrent refused: {}", text.trim());
+        }
+        Ok(status)
+    }
+}
+
+impl Sink for Qbittorrent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&self, magnet: &Magnet, delivery: &Delivery) -> anyhow::Result<()> {
+        // Versions before 5.0 call it paused; later versions call it stopped.
+        let paused = if delivery.paused { "true" } else { "false" };
+        let mut form = vec![
+            ("urls", magnet.link.as_str()),
+            ("paused", paused),
+            ("stopped", paused),
+        ];
+        if let Some(category) = delivery.category {
+            form.push(("category", category));
+        }
+        if let Some(save_path) = delivery.save_path {
+            form.push(("savepath", save_path));
+        }
+
+        let mut cookie = self.cookie.lock().unwrap();
+        if cookie.is_none() {
+            *cookie = self.login().context("qBittorrent login failed")?;
+        }
+
+        // Sessions expire; log in again once if we're turned away.
+        if self.add(cookie.as_deref(), &form)? == StatusCode::FORBIDDEN {
+            *cookie = self.login().context("qBittorrent login failed")?;
+            if self.add(cookie.as_deref(), &form)? == StatusCode::FORBIDDEN {
+                bail!("qBittorrent refused access");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiny_http::{Header, Response};
+
+    use super::Qbittorrent;
+    use crate::sink::{mock, Delivery, Sink, SinkConfig, SinkKind};
+
+    fn config(url: String) -> SinkConfig {
+        SinkConfig {
+            kind: SinkKind::Qbittorrent,
+            name: None,
+            url,
+            username: Some("admin".into()),
+            password: Some("secret".into()),
+        }
+    }
+
+    #[test]
+    fn logs_in_and_adds_magnets() {
+        let cookie = Header::from_bytes("Set-Cookie", "SID=abc123; HttpOnly; path=/").unwrap();
+        let (url, requests) = mock::serve(vec![
+            Response::from_string("Ok.").with_header(cookie),
+            Response::from_string("Ok."),
+            Response::from_string("Ok."),
+        ]);
+
+        let sink = Qbittorrent::new(&config(url.clone()));
+        let delivery = Delivery {
+            category: Some("scenes"),
+            save_path: Some("/downloads/scenes"),
+            paused: true,
+        };
+        sink.send(&mock::magnet(), &delivery).unwrap();
+        sink.send(&mock::magnet(), &Delivery::default()).unwrap();
+
+        let login = requests.recv().unwrap();
+        assert_eq!(login.url, "/api/v2/auth/login");
+        assert_eq!(login.body, "username=admin&password=secret");
+        assert_eq!(login.header("Referer"), Some(url.as_str()));
+
+        let add = requests.recv().unwrap();
+        assert_eq!(add.url, "/api/v2/torrents/add");
+        assert_eq!(add.header("Cookie"), Some("SID=abc123"));
+        assert!(add
+            .body
+            .starts_with("urls=magnet%3A%3Fxt%3Durn%3Abtih%3Af657aefd"));
+        assert!(add.body.contains("&paused=true&stopped=true"));
+        assert!(add.body.contains("&category=scenes"));
+        assert!(add.body.contains("&savepath=%2Fdownloads%2Fscenes"));
+
+        // The session is reused.
+        let add = requests.recv().unwrap();
+        assert_eq!(add.url, "/api/v2/torrents/add");
+        assert!(add.body.contains("&paused=false"));
+        assert!(!add.body.contains("category"));
+    }
+
+    #[test]
+    fn refused_torrents_are_errors() {
+        let (url, _requests) = mock::serve(vec![
+            Response::from_string("Fails."),
+            Response::from_string("Forbidden").with_status_code(403),
+            Response::from_string("Forbidden").with_status_code(403),
+        ]);
+
+        let mut config = config(url);
+        config.username = None;
+        let sink = Qbittorrent::new(&config);
+        assert!(sink.send(&mock::magnet(), &Delivery::default()).is_err());
+        assert!(sink.send(&mock::magnet(), &Delivery::default()).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::Mutex;
+
+use anyhow::bail;
+use reqwest::{blocking::Client, header::CONTENT_TYPE, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{Delivery, Sink, SinkConfig};
+use crate::magnet::Magnet;
+
+const SESSION_HEADER: &str = "X-Transmission-Session-Id";
+
+/// Transmission's JSON-RPC interface
+pub struct Transmission {
+    name: String,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    client: Client,
+    /// Transmission hands out a session id on the first request and expects it back after.
+    session: Mutex<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: String,
+}
+
+impl Transmission {
+    pub fn new(config: &SinkConfig) -> Self {
+        let url = config.url.trim_end_matches('/');
+        let url = if url.ends_with("/transmission/rpc") {
+            url.into()
+        } else {
+            format!("{url}/transmission/rpc")
+        };
+
+        Self {
+            name: config.name().into(),
+            url,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            client: super::build_client(),
+            session: Mutex::new(None),
+        }
+    }
+}
+
+impl Sink for Transmission {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&self, magnet: &Magnet, delivery: &Delivery) -> anyhow::Result<()> {
+        let mut arguments = json!({
+            "filename": magnet.link,
+            "paused": delivery.paused,
+        });
+        if let Some(save_path) = delivery.save_path {
+            arguments["download-dir"] = json!(save_path);
+        }
+        if let Some(category) = delivery.category {
+            arguments["labels"] = json!([category]);
+        }
+        let body = json!({ "method": "torrent-add", "arguments": arguments }).to_string();
+
+        let mut session = self.session.lock().unwrap();
+        for _ in 0..2 {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(username) = &self.username {
+                request = request.basic_auth(username, self.password.as_ref());
+            }
+            if let Some(session) = session.as_deref() {
+                request = request.header(SESSION_HEADER, session);
+            }
+
+            let response = request.send()?;
+            if response.status() == StatusCode::CONFLICT {
+                *session = response
+                    .headers()
+                    .get(SESSION_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .map(String::from);
+                continue;
+            }
+
+            let response: RpcResponse =
+                serde_json::from_str(&response.error_for_status()?.text()?)?;
+            if response.result != "success" {
+                bail!("torrent refused: {}", response.result);
+            }
+            return Ok(());
+        }
+
+        bail!("no Transmission session")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use tiny_http::{Header, Response};
+
+    use super::Transmission;
+    use crate::sink::{mock, Delivery, Sink, SinkConfig, SinkKind};
+
+    fn config(url: String) -> SinkConfig {
+        SinkConfig {
+            kind: SinkKind::Transmission,
+            name: Some("transmission".into()),
+            url,
+            username: Some("admin".into()),
+            password: Some("secret".into()),
+        }
+    }
+
+    #[test]
+    fn negotiates_a_session_and_adds_magnets() {
+        let session = Header::from_bytes("X-Transmission-Session-Id", "xyz").unwrap();
+        let (url, requests) = mock::serve(vec![
+            Response::from_string("")
+                .with_status_code(409)
+                .with_header(session),
+            Response::from_string(r#"{"result":"success","arguments":{"torrent-added":{}}}"#),
+        ]);
+
+        let sink = Transmission::new(&config(url));
+        let delivery = Delivery {
+            category: Some("scenes"),
+            save_path: Some("/downloads/scenes"),
+            paused: false,
+        };
+        sink.send(&mock::magnet(), &delivery).unwrap();
+
+        let first = requests.recv().unwrap();
+        assert_eq!(first.url, "/transmission/rpc");
+        assert_eq!(first.header("X-Transmission-Session-Id"), None);
+        assert_eq!(
+            first.header("Authorization"),
+            Some("Basic YWRtaW46c2VjcmV0")
+        );
+
+        let second = requests.recv().unwrap();
+        assert_eq!(second.header("X-Transmission-Session-Id"), Some("xyz"));
+        let body: Value = serde_json::from_str(&second.body).unwrap();
+        assert_eq!(body["method"], "torrent-add");
+        assert_eq!(body["arguments"]["filename"], mock::magnet().link.as_str());
+        assert_eq!(body["arguments"]["paused"], false);
+        assert_eq!(body["arguments"]["download-dir"], "/downloads/scenes");
+        assert_eq!(body["arguments"]["labels"][0], "scenes");
+    }
+
+    #[test]
+    fn failed_results_are_errors() {
+        let (url, _requests) = mock::serve(vec![Response::from_string(
+            r#"{"result":"invalid or corrupt torrent file","arguments":{}}"#,
+        )]);
+
+        let sink = Transmission::new(&config(format!("{url}/transmission/rpc/")));
+        let e = sink
+            .send(&mock::magnet(), &Delivery::default())
+            .unwrap_err();
+        assert!(e.to_string().contains("invalid or corrupt"));
+    }
+}
@@ -0,0 +1,203 @@
+use std::{cmp::Ordering, error, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+static UNITS: &[(&str, u64)] = &[
+    ("B", 1),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+];
+
+/// A size in bytes
+///
+/// The text the size was read from (e.g. "580.9 MiB") is kept for display, because the site's
+/// rounding is what people expect to see.
+#[derive(Clone, Debug)]
+pub struct Size {
+    bytes: u64,
+    text: String,
+}
+
+impl Size {
+    pub fn from_bytes(bytes: u64) -> Self {
+        let (unit, scale) = UNITS
+            .iter()
+            .rev()
+            .find(|&&(_, scale)| bytes >= scale)
+            .copied()
+            .unwrap_or(UNITS[0]);
+
+        let text = if scale == 1 {
+            format!("{bytes} {unit}")
+        } else {
+            format!("{:.2} {unit}", bytes as f64 / scale as f64)
+        };
+
+        Size { bytes, text }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl FromStr for Size {
+    type Err = ParseSizeErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 580.9&nbsp;MiB
+        // 580.9 MiB
+        // 580.9MiB
+
+        let text = s.replace("&nbsp;", " ");
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(|| ParseSizeErr::Unit(s.into()))?;
+        let (number, unit) = text.split_at(split);
+
+        let value: f64 = number.parse().map_err(|_| ParseSizeErr::Number(s.into()))?;
+        let scale = UNITS
+            .iter()
+            .find(|&&(name, _)| name.eq_ignore_ascii_case(unit.trim()))
+            .map(|&(_, scale)| scale)
+            .ok_or_else(|| ParseSizeErr::Unit(s.into()))?;
+
+        Ok(Size {
+            bytes: (value * scale as f64).round() as u64,
+            text: format!("{number} {}", unit.trim()),
+        })
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl PartialEq for Size {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for Size {}
+
+impl PartialOrd for Size {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Size {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes.cmp(&other.bytes)
+    }
+}
+
+// Sizes are written as a plain count of bytes, as in resource/test-input.csv, but may be read
+// back from either a count or a string like "580.9 MiB".
+
+impl Serialize for Size {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum SizeValue {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match SizeValue::deserialize(deserializer)? {
+            SizeValue::Bytes(bytes) => Ok(Size::from_bytes(bytes)),
+            SizeValue::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ParseSizeErr {
+    Number(String),
+    Unit(String),
+}
+
+impl fmt::Display for ParseSizeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSizeErr::Number(s) => write!(f, "bad size: {s}"),
+            ParseSizeErr::Unit(s) => {
+                write!(f, "bad size unit (expected B, KiB, MiB, GiB or TiB): {s}")
+            }
+        }
+    }
+}
+
+impl error::Error for ParseSizeErr {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseSizeErr, Size};
+
+    #[test]
+    fn can_parse_each_unit() {
+        static CASES: &[(&str, u64)] = &[
+            ("17&nbsp;B", 17),
+            ("1.5&nbsp;KiB", 1536),
+            ("580.9&nbsp;MiB", 609_117_798),
+            ("1.2&nbsp;GiB", 1_288_490_189),
+            ("2&nbsp;TiB", 2 << 40),
+            ("500 MiB", 500 << 20),
+            ("500mib", 500 << 20),
+        ];
+
+        for &(case, bytes) in CASES {
+            let size: Size = case.parse().unwrap();
+            assert_eq!(size.bytes(), bytes, "{case}");
+        }
+    }
+
+    #[test]
+    fn keeps_display_text() {
+        let size: Size = "580.9&nbsp;MiB".parse().unwrap();
+        assert_eq!(size.to_string(), "580.9 MiB");
+    }
+
+    #[test]
+    fn rejects_malformed_sizes() {
+        assert!(matches!(
+            "MiB".parse::<Size>(),
+            Err(ParseSizeErr::Number(_))
+        ));
+        assert!(matches!(
+            "1.2.3 MiB".parse::<Size>(),
+            Err(ParseSizeErr::Number(_))
+        ));
+        assert!(matches!(
+            "580.9".parse::<Size>(),
+            Err(ParseSizeErr::Unit(_))
+        ));
+        assert!(matches!(
+            "580.9 MB".parse::<Size>(),
+            Err(ParseSizeErr::Unit(_))
+        ));
+        assert!(matches!(
+            "-5 MiB".parse::<Size>(),
+            Err(ParseSizeErr::Number(_))
+        ));
+        assert!(matches!("".parse::<Size>(), Err(ParseSizeErr::Unit(_))));
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(Size::from_bytes(1000000).to_string(), "976.56 KiB");
+        assert_eq!(Size::from_bytes(12).to_string(), "12 B");
+    }
+}
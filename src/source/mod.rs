@@ -0,0 +1,79 @@
+mod tpb;
+
+use std::{error, fmt};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use scraper::Html;
+
+use crate::magnet::{ExtractMagnetContextErr, MagnetContext, ParseMagnetDateErr};
+
+/// An index site yogo knows how to scrape
+///
+/// A source is built from a single feed line and knows how to produce the links for each page
+/// of that feed, how to pull magnet contexts out of a results page, and how to read the dates
+/// the site prints for each upload.
+pub trait Source {
+    /// The scheme and host of the index, e.g. `https://thepiratebay10.org`.
+    fn origin(&self) -> &str;
+
+    /// The path (and query, if any) for a given page of results.
+    ///
+    /// Pagination begins with 1.
+    fn path(&self, page: usize) -> String;
+
+    /// Whether a page is one of the site's results pages, even one with no results on it.
+    fn is_results_page(&self, document: &Html) -> bool;
+
+    /// Reads each result row on a page.
+    ///
+    /// Rows are read independently, so one malformed row doesn't spoil the rest of the page.
+    fn extract<'a>(
+        &self,
+        document: &'a Html,
+    ) -> Vec<Result<MagnetContext<'a>, ExtractMagnetContextErr>>;
+
+    /// Reads the upload time from a row's info text.
+    ///
+    /// The site prints times in `tz`, and relative times ("5 mins ago") are taken from `now`.
+    fn parse_date(
+        &self,
+        info: &str,
+        tz: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, ParseMagnetDateErr>;
+}
+
+/// Why a page doesn't look like one of the site's results pages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unrecognized {
+    /// A captcha or "checking your browser" page stood in for the results.
+    Challenge,
+    /// Neither a results table nor the site's "no results" message.
+    NoResults,
+    /// There were result rows, but none of them could be read.
+    UnreadableRows,
+}
+
+impl fmt::Display for Unrecognized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unrecognized::Challenge => f.write_str("got a captcha or interstitial page"),
+            Unrecognized::NoResults => f.write_str("no results table"),
+            Unrecognized::UnreadableRows => f.write_str("none of the result rows could be read"),
+        }
+    }
+}
+
+impl error::Error for Unrecognized {}
+
+/// Builds a source for a feed url.
+///
+/// Each backend is offered the url in turn; the first to recognize it wins.
+pub fn from_url(url: &str) -> Option<Box<dyn Source>> {
+    let origin = Regex::new(r#"^(https?://[^/]+)"#).unwrap();
+    let origin = origin.captures(url)?.get(1)?.as_str();
+
+    tpb::from_url(origin, url).map(|source| Box::new(source) as Box<dyn Source>)
+}
@@ -0,0 +1,466 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::magnet::{ExtractMagnetContextErr, MagnetContext, ParseMagnetDateErr};
+
+use super::Source;
+
+enum Feed {
+    Search(String),
+    User(String),
+}
+
+/// The Pirate Bay and its many mirrors
+pub struct Tpb {
+    origin: String,
+    feed: Feed,
+    det_selector: Selector,
+    page_link_selector: Selector,
+    magnet_link_selector: Selector,
+    info_selector: Selector,
+    uploader_selector: Selector,
+    peer_selector: Selector,
+    kind_selector: Selector,
+    table_selector: Selector,
+    size_pattern: Regex,
+}
+
+pub fn from_url(origin: &str, url: &str) -> Option<Tpb> {
+    let search = Regex::new(r#"/search/([^/]+)"#).unwrap();
+    if let Some(cx) = search.captures(url) {
+        return cx
+            .get(1)
+            .map(|cx| Tpb::new(origin, Feed::Search(cx.as_str().into())));
+    }
+
+    let user = Regex::new(r#"/user/([^/]+)"#).unwrap();
+    if let Some(cx) = user.captures(url) {
+        return cx
+            .get(1)
+            .map(|cx| Tpb::new(origin, Feed::User(cx.as_str().into())));
+    }
+
+    None
+}
+
+impl Tpb {
+    fn new(origin: &str, feed: Feed) -> Self {
+        Tpb {
+            origin: origin.into(),
+            feed,
+            det_selector: Selector::parse("td > div.detName").unwrap(),
+            page_link_selector: Selector::parse("div.detName > a").unwrap(),
+            magnet_link_selector: Selector::parse("div.detName + a").unwrap(),
+            info_selector: Selector::parse("font").unwrap(),
+            uploader_selector: Selector::parse("font > a, font > i").unwrap(),
+            peer_selector: Selector::parse(r#"td[align="right"]"#).unwrap(),
+            kind_selector: Selector::parse("td.vertTh a").unwrap(),
+            table_selector: Selector::parse("table#searchResult").unwrap(),
+            size_pattern: Regex::new(r#"Size ([\d.]+&nbsp;[^,]+)"#).unwrap(),
+        }
+    }
+
+    fn extract_row<'a>(
+        &self,
+        element: &ElementRef<'a>,
+    ) -> Result<MagnetContext<'a>, ExtractMagnetContextErr> {
+        let info = self.get_info(element)?;
+        let size = self
+            .size_pattern
+            .captures(&info)
+            .ok_or_else(|| ExtractMagnetContextErr::Size(info.to_string()))?;
+
+        let size = size
+            .get(1)
+            .unwrap()
+            .as_str()
+            .parse()
+            .map_err(|_| ExtractMagnetContextErr::Size(info.to_string()))?;
+
+        // Seeders, leechers and the category live in the neighboring cells, so for these we
+        // need the whole row.
+        let row = element
+            .parent()
+            .and_then(ElementRef::wrap)
+            .ok_or_else(|| ExtractMagnetContextErr::Peers(element.html()))?;
+        let (seeders, leechers) = self.get_peers(&row)?;
+
+        Ok(MagnetContext {
+            text: self.get_link_text(element)?,
+            link: self.get_magnet_link(element)?,
+            size,
+            info: info.clone(),
+            seeders,
+            leechers,
+            uploader: self.get_uploader(element),
+            kind: self.get_kind(&row),
+        })
+    }
+
+    fn get_peers(&self, row: &ElementRef) -> Result<(u32, u32), ExtractMagnetContextErr> {
+        let mut counts = row
+            .select(&self.peer_selector)
+            .map(|cell| cell.text().collect::<String>().trim().parse::<u32>());
+
+        match (counts.next(), counts.next()) {
+            (Some(Ok(seeders)), Some(Ok(leechers))) => Ok((seeders, leechers)),
+            _ => Err(ExtractMagnetContextErr::Peers(row.html())),
+        }
+    }
+
+    /// Anonymous uploads have no uploader.
+    fn get_uploader(&self, element: &ElementRef) -> Option<String> {
+        let uploader = element
+            .select(&self.uploader_selector)
+            .next()?
+            .text()
+            .collect::<String>();
+        let uploader = uploader.trim();
+
+        if uploader.is_empty() || uploader.eq_ignore_ascii_case("anonymous") {
+            None
+        } else {
+            Some(uploader.into())
+        }
+    }
+
+    /// The category column reads e.g. "Porn (HD - Movies)"; we render that as
+    /// "Porn > HD - Movies".
+    fn get_kind(&self, row: &ElementRef) -> Option<String> {
+        let parts: Vec<String> = row
+            .select(&self.kind_selector)
+            .map(|link| link.text().collect::<String>().trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" > "))
+        }
+    }
+
+    fn get_link_text(&self, element: &ElementRef) -> Result<String, ExtractMagnetContextErr> {
+        let link_element = element
+            .select(&self.page_link_selector)
+            .next()
+            .ok_or_else(|| ExtractMagnetContextErr::PageLink(element.html()))?;
+        Ok(link_element.text().collect())
+    }
+
+    fn get_magnet_link<'a>(
+        &self,
+        element: &ElementRef<'a>,
+    ) -> Result<&'a str, ExtractMagnetContextErr> {
+        let link_element = element
+            .select(&self.magnet_link_selector)
+            .next()
+            .and_then(|element| element.value().attr("href"))
+            .ok_or_else(|| ExtractMagnetContextErr::MagnetLink(element.html()))?;
+        Ok(link_element)
+    }
+
+    fn get_info(&self, element: &ElementRef) -> Result<String, ExtractMagnetContextErr> {
+        let info_element = element
+            .select(&self.info_selector)
+            .next()
+            .ok_or_else(|| ExtractMagnetContextErr::Info(element.html()))?;
+        Ok(info_element.inner_html())
+    }
+}
+
+impl Source for Tpb {
+    fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    fn path(&self, page: usize) -> String {
+        match &self.feed {
+            // https://thepiratebay10.org/search/James%20Deen/1/3/0
+
+            // The path segment "3" refers to sorting by upload date.
+            // The last path segment refers to the search category.
+            Feed::Search(query) => format!("/search/{query}/{page}/3/0"),
+
+            // https://thepiratebay10.org/user/PornBaker/2/3
+
+            // Note the absence of the search category from above.
+            Feed::User(user) => format!("/user/{user}/{page}/3"),
+        }
+    }
+
+    /// Pages past the end of the results (and searches with none) say so instead of showing
+    /// an empty table.
+    fn is_results_page(&self, document: &Html) -> bool {
+        document.select(&self.table_selector).next().is_some()
+            || document
+                .root_element()
+                .text()
+                .any(|text| text.contains("No hits."))
+    }
+
+    fn extract<'a>(
+        &self,
+        document: &'a Html,
+    ) -> Vec<Result<MagnetContext<'a>, ExtractMagnetContextErr>> {
+        document
+            .select(&self.det_selector)
+            .filter_map(|element| ElementRef::wrap(element.parent()?))
+            .map(|element| self.extract_row(&element))
+            .collect()
+    }
+
+    fn parse_date(
+        &self,
+        info: &str,
+        tz: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, ParseMagnetDateErr> {
+        parse_uploaded(info, tz, now)
+    }
+}
+
+/// Reads the upload time from the info text, which comes in several forms:
+///
+/// ```text
+/// Uploaded <b>5&nbsp;mins&nbsp;ago</b>, Size 580.9&nbsp;MiB, ULed by
+/// Uploaded Today&nbsp;10:12, Size 580.9&nbsp;MiB, ULed by
+/// Uploaded Y-day&nbsp;03:44, Size 580.9&nbsp;MiB, ULed by
+/// Uploaded 05-02&nbsp;18:37, Size 580.9&nbsp;MiB, ULed by
+/// Uploaded 12-13&nbsp;2021, Size 487.16&nbsp;MiB, ULed by
+/// ```
+///
+/// Uploads from this year have a time of day; older ones only a date, which we take as midnight.
+fn parse_uploaded(
+    s: &str,
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, ParseMagnetDateErr> {
+    let text = s
+        .split(',')
+        .next()
+        .and_then(|text| text.trim().strip_prefix("Uploaded"))
+        .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?
+        .replace("&nbsp;", " ")
+        .replace("<b>", "")
+        .replace("</b>", "");
+    let tokens: Vec<_> = text.split_whitespace().collect();
+    let today = now.with_timezone(&tz).date_naive();
+    let integer = |token: &str| {
+        token
+            .parse::<u32>()
+            .map_err(|e| ParseMagnetDateErr::BadInteger(s.into(), e))
+    };
+
+    match tokens[..] {
+        [count, unit, "ago"] => {
+            let count = integer(count)?.into();
+            let elapsed = match unit.trim_end_matches('s') {
+                "sec" => Duration::seconds(count),
+                "min" => Duration::minutes(count),
+                "hour" => Duration::hours(count),
+                _ => return Err(ParseMagnetDateErr::BadSplit(s.into())),
+            };
+            Ok(now - elapsed)
+        }
+        ["Today", time] => local(s, tz, today, time),
+        ["Y-day", time] => {
+            let yesterday = today
+                .pred_opt()
+                .ok_or_else(|| ParseMagnetDateErr::NoSuchTime(s.into()))?;
+            local(s, tz, yesterday, time)
+        }
+        [date, time_or_year] => {
+            let (month, day) = date
+                .split_once('-')
+                .ok_or_else(|| ParseMagnetDateErr::BadSplit(s.into()))?;
+            let (month, day) = (integer(month)?, integer(day)?);
+            let date = |year| {
+                NaiveDate::from_ymd_opt(year, month, day)
+                    .ok_or_else(|| ParseMagnetDateErr::NoSuchTime(s.into()))
+            };
+
+            if !time_or_year.contains(':') {
+                let year = integer(time_or_year)? as i32;
+                return local(s, tz, date(year)?, "00:00");
+            }
+
+            // Uploads from this year have a time instead of a year. Just after new year, that
+            // can leave December's uploads looking like they're from the future.
+            let uploaded = local(s, tz, date(today.year())?, time_or_year)?;
+            if uploaded > now + Duration::days(1) {
+                local(s, tz, date(today.year() - 1)?, time_or_year)
+            } else {
+                Ok(uploaded)
+            }
+        }
+        _ => Err(ParseMagnetDateErr::BadSplit(s.into())),
+    }
+}
+
+/// A date and an "HH:MM" time in the site's timezone.
+fn local(
+    s: &str,
+    tz: Tz,
+    date: NaiveDate,
+    time: &str,
+) -> Result<DateTime<Utc>, ParseMagnetDateErr> {
+    let bad_date = || ParseMagnetDateErr::NoSuchTime(s.into());
+    let time = NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| bad_date())?;
+    tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|uploaded| uploaded.with_timezone(&Utc))
+        .ok_or_else(bad_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use chrono_tz::{Europe::Stockholm, Tz, UTC};
+    use scraper::Html;
+
+    use crate::{magnet::ExtractMagnetContextErr, source::Source};
+
+    static SEARCH: &str = include_str!("../../resource/fixtures/tpb/search.html");
+    static MISSING_SIZE: &str = include_str!("../../resource/fixtures/tpb/missing-size.html");
+
+    fn uploaded(date: &str, tz: Tz, now: DateTime<Utc>) -> DateTime<Utc> {
+        let info = format!("Uploaded {date}, Size 580.9&nbsp;MiB, ULed by");
+        super::parse_uploaded(&info, tz, now).unwrap()
+    }
+
+    #[test]
+    fn can_parse_magnet_date() {
+        let now = Utc.with_ymd_and_hms(2022, 6, 10, 12, 0, 0).unwrap();
+        let at = |m, d, h, min| Utc.with_ymd_and_hms(2022, m, d, h, min, 0).unwrap();
+
+        assert_eq!(uploaded("05-02&nbsp;18:37", UTC, now), at(5, 2, 18, 37));
+        assert_eq!(
+            uploaded("12-13&nbsp;2021", UTC, now),
+            Utc.with_ymd_and_hms(2021, 12, 13, 0, 0, 0).unwrap()
+        );
+        assert_eq!(uploaded("Today&nbsp;10:12", UTC, now), at(6, 10, 10, 12));
+        assert_eq!(uploaded("Y-day&nbsp;03:44", UTC, now), at(6, 9, 3, 44));
+        assert_eq!(
+            uploaded("<b>5&nbsp;mins&nbsp;ago</b>", UTC, now),
+            at(6, 10, 11, 55)
+        );
+        assert_eq!(
+            uploaded("<b>1&nbsp;min&nbsp;ago</b>", UTC, now),
+            at(6, 10, 11, 59)
+        );
+
+        for bad in [
+            "02-30&nbsp;2021",
+            "05-02&nbsp;25:00",
+            "Tomorrow&nbsp;10:12",
+            "soon",
+        ] {
+            let info = format!("Uploaded {bad}, Size 580.9&nbsp;MiB, ULed by");
+            assert!(super::parse_uploaded(&info, UTC, now).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn times_are_read_in_the_site_timezone() {
+        // Stockholm is two hours ahead of UTC in summer, and it's already tomorrow there.
+        let now = Utc.with_ymd_and_hms(2022, 6, 10, 23, 0, 0).unwrap();
+        let at = |m, d, h, min| Utc.with_ymd_and_hms(2022, m, d, h, min, 0).unwrap();
+
+        assert_eq!(
+            uploaded("05-02&nbsp;18:37", Stockholm, now),
+            at(5, 2, 16, 37)
+        );
+        assert_eq!(
+            uploaded("Today&nbsp;00:30", Stockholm, now),
+            at(6, 10, 22, 30)
+        );
+        assert_eq!(
+            uploaded("Y-day&nbsp;03:44", Stockholm, now),
+            at(6, 10, 1, 44)
+        );
+        assert_eq!(
+            uploaded("12-13&nbsp;2021", Stockholm, now),
+            Utc.with_ymd_and_hms(2021, 12, 12, 23, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn last_year_is_not_the_future() {
+        let now = Utc.with_ymd_and_hms(2023, 1, 1, 0, 10, 0).unwrap();
+        assert_eq!(
+            uploaded("12-31&nbsp;23:50", UTC, now),
+            Utc.with_ymd_and_hms(2022, 12, 31, 23, 50, 0).unwrap()
+        );
+        assert_eq!(
+            uploaded("01-01&nbsp;00:05", UTC, now),
+            Utc.with_ymd_and_hms(2023, 1, 1, 0, 5, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn builds_page_links() {
+        let search = super::from_url(
+            "https://thepiratebay10.org",
+            "https://thepiratebay10.org/search/James%20Deen/1/3/0",
+        )
+        .unwrap();
+        let user = super::from_url(
+            "https://thepiratebay10.org",
+            "https://thepiratebay10.org/user/PornBaker/",
+        )
+        .unwrap();
+
+        assert_eq!(search.origin(), "https://thepiratebay10.org");
+        assert_eq!(search.path(2), "/search/James%20Deen/2/3/0");
+        assert_eq!(user.path(1), "/user/PornBaker/1/3");
+    }
+
+    #[test]
+    fn can_extract_search_page() {
+        let source = super::from_url("https://tpb.example", "/search/sample/1/3/0").unwrap();
+        let document = Html::parse_fragment(SEARCH);
+        let contexts: Vec<_> = source
+            .extract(&document)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(contexts.len(), 3);
+        assert_eq!(contexts[0].text, "Sample Release 2022 1080p");
+        assert!(contexts[0]
+            .link
+            .starts_with("magnet:?xt=urn:btih:F657AEFD637DBCC4E3AC965DDB7CCA0C11BC157C"));
+        assert_eq!(contexts[0].size.to_string(), "580.9 MiB");
+        assert_eq!(contexts[1].size.bytes(), 1_288_490_189);
+        assert_eq!(contexts[2].size.to_string(), "487.16 MiB");
+
+        assert_eq!(
+            source
+                .parse_date(&contexts[2].info, UTC, Utc::now())
+                .unwrap(),
+            Utc.with_ymd_and_hms(2021, 12, 13, 0, 0, 0).unwrap()
+        );
+
+        assert_eq!((contexts[0].seeders, contexts[0].leechers), (42, 7));
+        assert_eq!((contexts[1].seeders, contexts[1].leechers), (5, 0));
+        assert_eq!(contexts[0].uploader.as_deref(), Some("PornBaker"));
+        assert_eq!(contexts[2].uploader, None);
+        assert_eq!(contexts[0].kind.as_deref(), Some("Porn > HD - Movies"));
+        assert_eq!(contexts[1].kind.as_deref(), Some("Porn > Movie clips"));
+    }
+
+    #[test]
+    fn bad_rows_do_not_spoil_the_page() {
+        let source = super::from_url("https://tpb.example", "/search/sample/1/3/0").unwrap();
+        let document = Html::parse_fragment(MISSING_SIZE);
+        let contexts = source.extract(&document);
+
+        assert_eq!(contexts.len(), 3);
+        assert!(contexts[0].is_ok());
+        assert!(matches!(contexts[1], Err(ExtractMagnetContextErr::Size(_))));
+        assert!(contexts[2].is_ok());
+    }
+}
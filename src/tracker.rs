@@ -0,0 +1,127 @@
+use serde::Deserialize;
+
+use crate::{
+    filter::Rule,
+    magnet::{Magnet, MagnetUri},
+};
+
+/// What to do with the trackers listed in each magnet link
+///
+/// Trackers are stripped (all of them, or those matching a blocklist rule) and then our own are
+/// appended. Stripping all and adding our own replaces the list entirely.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackerPolicy {
+    /// Drop every tracker the index listed.
+    #[serde(default)]
+    pub strip: bool,
+    /// Drop trackers matching any of these rules.
+    #[serde(default)]
+    pub block: Vec<Rule>,
+    /// Trackers to append to every link.
+    #[serde(default)]
+    pub add: Vec<String>,
+}
+
+impl TrackerPolicy {
+    pub fn is_empty(&self) -> bool {
+        !self.strip && self.block.is_empty() && self.add.is_empty()
+    }
+
+    /// Rewrites a magnet's link. Links which can't be parsed are left as they are.
+    pub fn apply(&self, magnet: &mut Magnet) {
+        if self.is_empty() {
+            return;
+        }
+
+        let Ok(mut uri) = magnet.link.parse::<MagnetUri>() else {
+            return;
+        };
+
+        if self.strip {
+            uri.trackers.clear();
+        }
+        uri.trackers
+            .retain(|tracker| !self.block.iter().any(|rule| rule.is_match(tracker)));
+        for tracker in &self.add {
+            if !uri.trackers.contains(tracker) {
+                uri.trackers.push(tracker.clone());
+            }
+        }
+
+        magnet.link = uri.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackerPolicy;
+    use crate::magnet::{Magnet, MagnetUri};
+
+    static OURS: &str = "udp://tracker.example:1337/announce";
+
+    fn magnet() -> Magnet {
+        // The first link from the test input, with its eleven trackers.
+        let link = include_str!("../resource/test-input.csv")
+            .lines()
+            .nth(1)
+            .and_then(|line| line.split(',').nth(2))
+            .unwrap();
+
+        Magnet {
+            link: link.into(),
+            ..Magnet::sample()
+        }
+    }
+
+    fn trackers(policy: &TrackerPolicy) -> Vec<String> {
+        let mut magnet = magnet();
+        policy.apply(&mut magnet);
+        magnet.link.parse::<MagnetUri>().unwrap().trackers
+    }
+
+    #[test]
+    fn empty_policy_leaves_links_alone() {
+        let mut magnet = magnet();
+        TrackerPolicy::default().apply(&mut magnet);
+        assert_eq!(magnet.link, self::magnet().link);
+        assert_eq!(trackers(&TrackerPolicy::default()).len(), 11);
+    }
+
+    #[test]
+    fn strips_all_or_blocked_trackers() {
+        let policy = TrackerPolicy {
+            strip: true,
+            ..Default::default()
+        };
+        assert!(trackers(&policy).is_empty());
+
+        let policy = TrackerPolicy {
+            block: vec!["coppersurfer".parse().unwrap(), "/:6969/".parse().unwrap()],
+            ..Default::default()
+        };
+        let trackers = trackers(&policy);
+        assert!(!trackers.is_empty());
+        assert!(trackers
+            .iter()
+            .all(|tracker| !tracker.contains("coppersurfer") && !tracker.contains(":6969")));
+    }
+
+    #[test]
+    fn appends_or_replaces_trackers() {
+        let policy = TrackerPolicy {
+            add: vec![OURS.into(), "udp://tracker.opentrackr.org:1337".into()],
+            ..Default::default()
+        };
+        let trackers = trackers(&policy);
+        assert_eq!(trackers.len(), 12);
+        assert_eq!(trackers.last().map(String::as_str), Some(OURS));
+
+        let policy = TrackerPolicy {
+            strip: true,
+            add: vec![OURS.into()],
+            ..Default::default()
+        };
+        assert_eq!(self::trackers(&policy), [OURS]);
+    }
+}
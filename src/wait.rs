@@ -1,8 +1,11 @@
 use std::{
+    sync::{Arc, Mutex},
     thread,
     time::{Duration, Instant},
 };
 
+use hashbrown::HashMap;
+
 const DEFAULT_WAIT_TIME: u64 = 500;
 
 #[derive(Clone, Debug)]
@@ -41,3 +44,66 @@ impl Default for Waiter {
         Waiter::new()
     }
 }
+
+/// Paces requests separately for each host
+///
+/// Each host gets its own Waiter, so requests to one mirror or backend never wait on requests
+/// to another. Callers bound for the same host queue up behind one another.
+#[derive(Debug)]
+pub struct HostWaiter {
+    template: Waiter,
+    hosts: Mutex<HashMap<String, Arc<Mutex<Waiter>>>>,
+}
+
+impl HostWaiter {
+    pub fn new(waiter: Waiter) -> Self {
+        Self {
+            template: waiter,
+            hosts: Default::default(),
+        }
+    }
+
+    pub fn wait(&self, url: &str) {
+        let waiter = {
+            let mut hosts = self.hosts.lock().unwrap();
+            hosts
+                .entry(host(url).into())
+                .or_insert_with(|| Arc::new(Mutex::new(self.template.clone())))
+                .clone()
+        };
+
+        waiter.lock().unwrap().wait();
+    }
+}
+
+/// Everything after the scheme and before the path, e.g. `thepiratebay10.org`.
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{HostWaiter, Waiter};
+
+    #[test]
+    fn hosts_are_paced_separately() {
+        let waiter = HostWaiter::new(Waiter::with_wait(200));
+        let start = Instant::now();
+
+        waiter.wait("https://a.example/search/1");
+        waiter.wait("https://b.example/search/1");
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        waiter.wait("https://a.example/search/2");
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn host_ignores_scheme_and_path() {
+        assert_eq!(super::host("https://tpb.example/user/x/1/3"), "tpb.example");
+        assert_eq!(super::host("http://tpb.example"), "tpb.example");
+    }
+}